@@ -2,10 +2,13 @@
 extern crate regex;
 
 use std::env;
+use std::io;
 use std::process;
 
+use oak::interpreter::Interpreter;
+use oak::parser::{ScriptError, Value};
 use oak::repl::start_repl;
-use oak::runtime::run;
+use oak::runtime::run_with_output;
 use regex::Regex;
 
 fn main() {
@@ -27,6 +30,11 @@ fn main() {
             call_for_help();
             process::exit(0);
         }
+        // `-d script.oak` runs the script tracing every evaluation step
+        "-d" if args.len() > 2 && script_argument_re.is_match(&args[2]) => {
+            let mut interpreter = Interpreter::new().with_verbose(true);
+            exit_with(run_script(&args[2], &mut interpreter));
+        }
         "-d" => {
             debug_mode = true;
         }
@@ -36,30 +44,36 @@ fn main() {
         // If no flags are passed to the binary, it will run the script passed to the cli
         argument_string => {
             if script_argument_re.is_match(argument_string) {
-                let executed_script = run(argument_string.to_string());
-
-                if executed_script.is_err() {
-                    println!("FATAL ERROR while trying to run script. Exiting.");
-                    process::exit(1);
-                } else {
-                    process::exit(0);
-                }
+                exit_with(run_script(argument_string, &mut Interpreter::new()));
             }
         }
     }
 
-    let result = if debug_mode {
+    if debug_mode {
         println!("Implement this function...");
     } else {
         call_for_help();
         println!("Implement rest of the code...");
-    };
+    }
+}
 
-    println!("Result: {:?}", result);
+// Runs the script at `path`, printing the value of each statement that has one
+fn run_script(path: &str, interpreter: &mut Interpreter) -> Result<Value, ScriptError> {
+    println!("Running script with Oak version 0.1.0...");
+    run_with_output(path.to_string(), interpreter, &mut io::stdout())
+}
+
+fn exit_with<T>(executed_script: Result<T, ScriptError>) -> ! {
+    if let Err(error) = executed_script {
+        println!("FATAL ERROR while trying to run script: {}. Exiting.", error);
+        process::exit(1);
+    } else {
+        process::exit(0);
+    }
 }
 
 fn call_for_help() {
-    println!("");
+    println!();
     println!("⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡠⠖⠒⠢⣄⣀⡀⣀⣀⠀⡠⠔⠒⠒⢤⡀⠀⠀⠀⠀⠀⠀Oak Programming Language");
     println!("⠀⠀⠀⠀⠀⠀⠀⠀⠀⢀⡴⡇⠀⠀⠀⠁⠠⡋⠀⠀⠙⠦⠀⠀⠀⠀⣧⠤⣀⠀⠀⠀⠀");
     println!("⠀⠀⠀⠀⠀⠀⠀⡠⠖⠊⠑⠲⣄⣀⣠⠖⠘⠛⠀⠀⠀⠀⠀⠀⠀⠀⠁⠀⢸⠇⠀⠀⠀");
@@ -78,7 +92,7 @@ fn call_for_help() {
     println!("⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢀⡼⠁⠀⠀⠀⠀⠈⣇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀");
     println!("⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⣠⡴⠒⢋⣁⡀⠀⠀⠀⠀⠀⠘⠢⢄⣀⠀⠀⠀⠀⠀⠀⠀⠀");
     println!("⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠉⠉⠉⠉⠁⠉⠙⠒⠤⣘⣗⠒⠒⠒⠚⠛⠃⠀⠀⠀⠀⠀⠀");
-    println!("");
+    println!();
     println!("Usage: oak <script.oak> or oak -h for help");
-    println!("Available flags: -h (help) -d (debug) -c (compile) -r (REPL)");
+    println!("Available flags: -h (help) -d <script.oak> (debug trace) -c (compile) -r (REPL)");
 }
@@ -1,3 +1,361 @@
+// Compiler: lowers the AST into stack-based bytecode, and the VM that runs it
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::interpreter::{aggregate, apply_bin_op, apply_unary_op, is_aggregate, AngleMode, RuntimeError};
+use crate::math::{
+    get_binary_math_functions, get_math_constants, get_math_functions, get_ternary_math_functions,
+};
+use crate::parser::{
+    Assign, BinOp, Comment, Discard, EvalMathExp, FunctionCall, FunctionDef, If, Integer, Node, Number,
+    StringLiteral, UnaryOp, Value, Var, Visitor, While,
+};
+
+/// Instructions of the stack machine
+///
+/// Operands are taken from the top of the stack, the right one on top,
+/// and the result is pushed back.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    /// Push a number
+    PushNum(f64),
+    /// Push an integer
+    PushInt(i64),
+    /// Push the value of a variable
+    LoadVar(String),
+    /// Declare a variable holding the top of the stack, leaving it on the stack
+    /// as the value of the declaration. Fails if the variable already exists.
+    DeclareVar(String),
+    /// Store the top of the stack in an existing variable, leaving it on the
+    /// stack as the value of the assignment
+    StoreVar(String),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// Negate the value on top of the stack
+    Neg,
+    /// Call a function with the given number of arguments, pushed in order
+    Call(String, usize),
+    /// Drop the value on top of the stack, that of a statement ended by `;`
+    Pop,
+}
+
+/// Errors raised while compiling an AST
+#[derive(Error, Debug, PartialEq)]
+pub enum CompileError {
+    #[error("Cannot compile {0} to bytecode")]
+    Unsupported(String),
+}
+
+/// Compile a sequence of statements into bytecode
+///
+/// # Returns
+/// * `Ok(Vec<Op>)` - The instructions of every statement, in order
+/// * `Err(CompileError)` if a statement uses a construct the bytecode cannot express
+pub fn compile(nodes: &[Box<dyn Node>]) -> Result<Vec<Op>, CompileError> {
+    let mut compiler = Compiler::default();
+    for node in nodes {
+        // The compiler records its own errors, visiting never fails
+        let _ = node.accept(&mut compiler);
+    }
+    match compiler.error {
+        Some(error) => Err(error),
+        None => Ok(compiler.ops),
+    }
+}
+
+// AST visitor emitting the instructions of each node it visits
+#[derive(Default)]
+struct Compiler {
+    ops: Vec<Op>,
+    // First construct that could not be compiled
+    error: Option<CompileError>,
+}
+
+impl Compiler {
+    fn unsupported(&mut self, what: String) -> Result<Value, RuntimeError> {
+        if self.error.is_none() {
+            self.error = Some(CompileError::Unsupported(what));
+        }
+        Ok(Value::None)
+    }
+}
+
+impl Visitor for Compiler {
+    fn visit_eval_math_exp(&mut self, _node: &EvalMathExp) -> Result<Value, RuntimeError> {
+        self.unsupported("a math expression string".to_string())
+    }
+
+    fn visit_bin_op(&mut self, node: &BinOp) -> Result<Value, RuntimeError> {
+        let op = match node.op.as_str() {
+            "+" => Op::Add,
+            "-" => Op::Sub,
+            "*" => Op::Mul,
+            "/" => Op::Div,
+            other => return self.unsupported(format!("operator '{}'", other)),
+        };
+        node.left.accept(self)?;
+        node.right.accept(self)?;
+        self.ops.push(op);
+        Ok(Value::None)
+    }
+
+    fn visit_unary_op(&mut self, node: &UnaryOp) -> Result<Value, RuntimeError> {
+        node.operand.accept(self)?;
+        match node.op.as_str() {
+            "-" => self.ops.push(Op::Neg),
+            // Unary plus leaves its operand as is
+            "+" => {}
+            other => return self.unsupported(format!("operator '{}'", other)),
+        }
+        Ok(Value::None)
+    }
+
+    fn visit_number(&mut self, node: &Number) -> Result<Value, RuntimeError> {
+        self.ops.push(Op::PushNum(node.value));
+        Ok(Value::None)
+    }
+
+    fn visit_integer(&mut self, node: &Integer) -> Result<Value, RuntimeError> {
+        self.ops.push(Op::PushInt(node.value));
+        Ok(Value::None)
+    }
+
+    fn visit_var(&mut self, node: &Var) -> Result<Value, RuntimeError> {
+        self.ops.push(Op::LoadVar(node.name.clone()));
+        Ok(Value::None)
+    }
+
+    fn visit_assign(&mut self, node: &Assign) -> Result<Value, RuntimeError> {
+        node.expr.accept(self)?;
+        self.ops.push(if node.is_declaration {
+            Op::DeclareVar(node.name.clone())
+        } else {
+            Op::StoreVar(node.name.clone())
+        });
+        Ok(Value::None)
+    }
+
+    fn visit_string_literal(&mut self, _node: &StringLiteral) -> Result<Value, RuntimeError> {
+        self.unsupported("a string literal".to_string())
+    }
+
+    fn visit_function_call(&mut self, node: &FunctionCall) -> Result<Value, RuntimeError> {
+        for arg in &node.args {
+            arg.accept(self)?;
+        }
+        self.ops.push(Op::Call(node.name.clone(), node.args.len()));
+        Ok(Value::None)
+    }
+
+    fn visit_function_def(&mut self, node: &FunctionDef) -> Result<Value, RuntimeError> {
+        self.unsupported(format!("function definition '{}'", node.name))
+    }
+
+    fn visit_if(&mut self, _node: &If) -> Result<Value, RuntimeError> {
+        self.unsupported("an if expression".to_string())
+    }
+
+    fn visit_while(&mut self, _node: &While) -> Result<Value, RuntimeError> {
+        self.unsupported("a while loop".to_string())
+    }
+
+    fn visit_comment(&mut self, _node: &Comment) -> Result<Value, RuntimeError> {
+        Ok(Value::None)
+    }
+
+    fn visit_discard(&mut self, node: &Discard) -> Result<Value, RuntimeError> {
+        node.statement.accept(self)?;
+        self.ops.push(Op::Pop);
+        Ok(Value::None)
+    }
+}
+
+/// Stack machine executing compiled bytecode
+///
+/// Variables persist between runs. Declarations, arithmetic and function
+/// calls, including the angle mode, follow the same rules as the tree-walking
+/// `Interpreter`. Being a stack machine, it evaluates the arguments of a call
+/// before checking their count.
+pub struct VM {
+    stack: Vec<Value>,
+    variables: HashMap<String, Value>,
+    angle_mode: AngleMode,
+    math_functions: HashMap<String, fn(f64) -> f64>,
+    binary_math_functions: HashMap<String, fn(f64, f64) -> f64>,
+    ternary_math_functions: HashMap<String, fn(f64, f64, f64) -> f64>,
+    math_constants: HashMap<String, f64>,
+}
+
+impl VM {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            variables: HashMap::new(),
+            angle_mode: AngleMode::from_env(),
+            math_functions: get_math_functions(),
+            binary_math_functions: get_binary_math_functions(),
+            ternary_math_functions: get_ternary_math_functions(),
+            math_constants: get_math_constants(),
+        }
+    }
+
+    /// Variables stored so far, by name
+    pub fn variables(&self) -> &HashMap<String, Value> {
+        &self.variables
+    }
+
+    /// Unit of the angles used by the trigonometric functions
+    pub fn angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    /// Switch the trigonometric functions between radians and degrees
+    pub fn set_angle_mode(&mut self, mode: AngleMode) {
+        self.angle_mode = mode;
+    }
+
+    /// Execute a sequence of instructions
+    ///
+    /// # Returns
+    /// * `Ok(Value)` - The value left on top of the stack, or `Value::None` if it is empty
+    /// * `Err(RuntimeError)` if an instruction fails
+    pub fn run(&mut self, ops: &[Op]) -> Result<Value, RuntimeError> {
+        self.stack.clear();
+        for op in ops {
+            self.execute(op)?;
+        }
+        Ok(self.stack.last().cloned().unwrap_or(Value::None))
+    }
+
+    fn execute(&mut self, op: &Op) -> Result<(), RuntimeError> {
+        match op {
+            Op::PushNum(value) => self.stack.push(Value::Number(*value)),
+            Op::PushInt(value) => self.stack.push(Value::Integer(*value)),
+            Op::LoadVar(name) => {
+                // Constants take precedence over variables, as in the interpreter
+                let value = match self.math_constants.get(name) {
+                    Some(&constant) => Value::Number(constant),
+                    None => self
+                        .variables
+                        .get(name)
+                        .cloned()
+                        .ok_or_else(|| RuntimeError::UndefinedVariable(name.clone()))?,
+                };
+                self.stack.push(value);
+            }
+            Op::DeclareVar(name) | Op::StoreVar(name) => {
+                let value = self.peek("store")?.clone();
+                if value.as_number().is_none() {
+                    return Err(RuntimeError::TypeMismatch {
+                        op: ":=".to_string(),
+                    });
+                }
+                let declared = self.variables.contains_key(name);
+                match op {
+                    Op::DeclareVar(_) if declared => return Err(RuntimeError::AlreadyDeclared(name.clone())),
+                    Op::StoreVar(_) if !declared => {
+                        return Err(RuntimeError::UndeclaredAssignment(name.clone()))
+                    }
+                    _ => {}
+                }
+                self.variables.insert(name.clone(), value);
+            }
+            Op::Add => self.bin_op("+")?,
+            Op::Sub => self.bin_op("-")?,
+            Op::Mul => self.bin_op("*")?,
+            Op::Div => self.bin_op("/")?,
+            Op::Neg => {
+                let operand = self.pop("-")?;
+                self.stack.push(apply_unary_op("-", &operand)?);
+            }
+            Op::Call(name, argc) => {
+                let result = self.call(name, *argc)?;
+                self.stack.push(result);
+            }
+            Op::Pop => {
+                self.pop(";")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn peek(&self, instruction: &str) -> Result<&Value, RuntimeError> {
+        self.stack
+            .last()
+            .ok_or_else(|| RuntimeError::StackUnderflow(instruction.to_string()))
+    }
+
+    fn pop(&mut self, instruction: &str) -> Result<Value, RuntimeError> {
+        self.stack
+            .pop()
+            .ok_or_else(|| RuntimeError::StackUnderflow(instruction.to_string()))
+    }
+
+    fn bin_op(&mut self, op: &str) -> Result<(), RuntimeError> {
+        let right = self.pop(op)?;
+        let left = self.pop(op)?;
+        let result = apply_bin_op(op, &left, &right)?;
+        self.stack.push(result);
+        Ok(())
+    }
+
+    // Pops the arguments of a call and dispatches to the math function of that name
+    fn call(&mut self, name: &str, argc: usize) -> Result<Value, RuntimeError> {
+        if self.stack.len() < argc {
+            return Err(RuntimeError::StackUnderflow(name.to_string()));
+        }
+        let values = self.stack.split_off(self.stack.len() - argc);
+        // `min`, `max`, `sum`, `mean` and `stddev` take any number of arguments
+        if is_aggregate(name) {
+            if values.iter().any(|value| value.as_number().is_none()) {
+                return Err(RuntimeError::TypeMismatch { op: name.to_string() });
+            }
+            return aggregate(name, &values);
+        }
+        let mut args = Vec::with_capacity(argc);
+        for value in &values {
+            match value.as_number() {
+                Some(x) => args.push(x),
+                None => return Err(RuntimeError::TypeMismatch { op: name.to_string() }),
+            }
+        }
+
+        let check_arity = |expected: usize| {
+            if argc == expected {
+                Ok(())
+            } else {
+                Err(RuntimeError::WrongArity {
+                    func: name.to_string(),
+                    expected,
+                    got: argc,
+                })
+            }
+        };
+        if let Some(&math_func) = self.math_functions.get(name) {
+            check_arity(1)?;
+            return Ok(Value::Number(self.angle_mode.call_unary(name, math_func, args[0])));
+        }
+        if let Some(&math_func) = self.binary_math_functions.get(name) {
+            check_arity(2)?;
+            return Ok(Value::Number(self.angle_mode.call_binary(name, math_func, args[0], args[1])));
+        }
+        if let Some(&math_func) = self.ternary_math_functions.get(name) {
+            check_arity(3)?;
+            return Ok(Value::Number(math_func(args[0], args[1], args[2])));
+        }
+        Err(RuntimeError::UnknownFunction(name.to_string()))
+    }
+}
+
+impl Default for VM {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[allow(dead_code)]
 fn compile_to_x86_64() {
     println!("To be implemented...");
@@ -1,130 +1,756 @@
 // Interpreter / AST Visitor
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use thiserror::Error;
 
 use super::parser::{
-    Assign, BinOp, Comment, EvalMathExp, FunctionCall, Number, StringLiteral, Value, Var, Visitor,
+    parse_expr, Assign, BinOp, Comment, Discard, EvalMathExp, FunctionCall, FunctionDef, If, Integer,
+    Node, Number, StringLiteral, UnaryOp, Value, Var, Visitor, While, DEFAULT_EPSILON,
+};
+use super::math::{
+    calc_architecture_command, get_binary_math_functions, get_math_functions, get_math_constants,
+    get_ternary_math_functions, MathModule, AGGREGATE_FUNCTIONS,
 };
-use super::math::{get_math_functions, get_math_constants};
+use super::tokenizer::tokenize;
+
+/// Errors raised while evaluating an AST
+#[derive(Error, Debug, PartialEq)]
+pub enum RuntimeError {
+    #[error("Undefined variable '{0}'")]
+    UndefinedVariable(String),
+    #[error("Type mismatch in '{op}'")]
+    TypeMismatch { op: String },
+    #[error("Unknown operator '{0}'")]
+    UnknownOperator(String),
+    #[error("Unknown function '{0}'")]
+    UnknownFunction(String),
+    #[error("Function '{func}' expects {expected} argument(s), got {got}")]
+    WrongArity {
+        func: String,
+        expected: usize,
+        got: usize,
+    },
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Integer overflow in '{op}'")]
+    IntegerOverflow { op: String },
+    #[error("Stack underflow while executing '{0}'")]
+    StackUnderflow(String),
+    #[error("Maximum recursion depth of {limit} exceeded in '{func}'")]
+    RecursionLimitExceeded { func: String, limit: usize },
+    #[error("Function '{0}' expects at least one argument")]
+    MissingArguments(String),
+    #[error("Loop exceeded the maximum of {limit} iterations")]
+    IterationLimitExceeded { limit: usize },
+    #[error("Invalid math expression '{expr}': {message}")]
+    InvalidExpression { expr: String, message: String },
+    #[error("Variable '{0}' is already declared")]
+    AlreadyDeclared(String),
+    #[error("Cannot assign to undeclared variable '{0}', declare it with 'var' first")]
+    UndeclaredAssignment(String),
+    #[error("Cannot assign to global variable '{0}' inside a function, declare a local one with 'var'")]
+    GlobalAssignment(String),
+}
+
+/// Maximum number of nested user-defined function calls
+pub const MAX_CALL_DEPTH: usize = 200;
+
+/// Default maximum number of iterations of a single `while` loop
+pub const DEFAULT_MAX_ITERATIONS: usize = 1_000_000;
+
+/// Default maximum nesting depth of the nodes evaluated at once
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// Environment variable setting the angle mode new interpreters start in, `deg` or `rad`
+pub const ANGLE_MODE_VAR: &str = "OAK_ANGLE_MODE";
+
+/// Unit of the angles taken by `sin`, `cos` and `tan`, and returned by their inverses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AngleMode {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl std::fmt::Display for AngleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AngleMode::Radians => write!(f, "radians"),
+            AngleMode::Degrees => write!(f, "degrees"),
+        }
+    }
+}
+
+impl AngleMode {
+    // The mode named by `OAK_ANGLE_MODE`, radians when it is unset or invalid
+    pub(crate) fn from_env() -> Self {
+        std::env::var(ANGLE_MODE_VAR)
+            .ok()
+            .and_then(|mode| mode.parse().ok())
+            .unwrap_or_default()
+    }
+
+    // Calls a single-argument math function, converting the angles taken by
+    // `sin`, `cos` and `tan` and returned by their inverses to this unit
+    pub(crate) fn call_unary(self, name: &str, f: fn(f64) -> f64, x: f64) -> f64 {
+        match (self, name) {
+            (AngleMode::Degrees, "sin" | "cos" | "tan") => f(x.to_radians()),
+            (AngleMode::Degrees, "asin" | "acos" | "atan") => f(x).to_degrees(),
+            _ => f(x),
+        }
+    }
+
+    // Calls a two-argument math function, converting the angle returned by `atan2`
+    pub(crate) fn call_binary(self, name: &str, f: fn(f64, f64) -> f64, a: f64, b: f64) -> f64 {
+        match (self, name) {
+            (AngleMode::Degrees, "atan2") => f(a, b).to_degrees(),
+            _ => f(a, b),
+        }
+    }
+}
+
+impl std::str::FromStr for AngleMode {
+    type Err = String;
+
+    /// Parse the short names `deg` and `rad`
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "deg" => Ok(AngleMode::Degrees),
+            "rad" => Ok(AngleMode::Radians),
+            other => Err(format!("Unknown angle mode '{}', expected deg or rad", other)),
+        }
+    }
+}
+
+// A function defined in a script with `fn name(params) := body`
+struct UserFunction {
+    params: Vec<String>,
+    body: Rc<dyn Node>,
+}
 
 pub struct Interpreter {
-    variables: HashMap<String, f64>,
+    // Variable scopes: the global scope first, then one scope per user-defined
+    // function call in progress, innermost last
+    scopes: Vec<HashMap<String, Value>>,
     math_functions: HashMap<String, fn(f64) -> f64>,
+    binary_math_functions: HashMap<String, fn(f64, f64) -> f64>,
+    ternary_math_functions: HashMap<String, fn(f64, f64, f64) -> f64>,
     math_constants: HashMap<String, f64>,
+    user_functions: HashMap<String, UserFunction>,
+    angle_mode: AngleMode,
+    // Iterations a single loop may run before it is stopped with an error
+    max_iterations: usize,
+    // Whether each evaluation step is traced to stdout
+    verbose: bool,
+    // Nodes being evaluated, one inside the other, and how many are allowed
+    depth: usize,
+    max_depth: usize,
+    // Global variables that `var` may declare once more, assigning them
+    redeclarable: HashSet<String>,
 }
 
 impl Interpreter {
+    /// Create an interpreter with an empty global scope
+    ///
+    /// It starts in the angle mode named by the `OAK_ANGLE_MODE` environment
+    /// variable (`deg` or `rad`), or in radians when it is unset or invalid.
     pub fn new() -> Self {
         Self {
-            variables: HashMap::new(),
+            scopes: vec![HashMap::new()],
             math_functions: get_math_functions(),
+            binary_math_functions: get_binary_math_functions(),
+            ternary_math_functions: get_ternary_math_functions(),
             math_constants: get_math_constants(),
+            user_functions: HashMap::new(),
+            angle_mode: AngleMode::from_env(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            verbose: false,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            redeclarable: HashSet::new(),
         }
     }
-}
 
-impl Visitor for Interpreter {
-    fn visit_eval_math_exp(&mut self, node: &EvalMathExp) -> Value {
-        println!("Evaluando expresión matemática: {}", node.expr);
-        Value::None
-    }
-
-    fn visit_bin_op(&mut self, node: &BinOp) -> Value {
-        let left = node.left.accept(self);
-        let right = node.right.accept(self);
-
-        match (left, right) {
-            (Value::Number(l), Value::Number(r)) => match node.op.as_str() {
-                "+" => Value::Number(l + r),
-                "-" => Value::Number(l - r),
-                "*" => Value::Number(l * r),
-                "/" => Value::Number(l / r),
-                _ => {
-                    println!("Operación desconocida: {}", node.op);
-                    Value::None
-                }
-            },
-            _ => {
-                println!("Error de tipo en operación binaria");
-                Value::None
+    /// Trace every evaluation step (variable reads, assignments, calls and
+    /// their results) to stdout, for debugging scripts
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Whether evaluation steps are traced to stdout
+    pub fn is_verbose(&self) -> bool {
+        self.verbose
+    }
+
+    /// Limit the number of iterations of any single `while` loop, so runaway
+    /// loops end with an error instead of hanging
+    pub fn set_max_iterations(&mut self, limit: usize) {
+        self.max_iterations = limit;
+    }
+
+    /// Limit how deeply nodes may nest during evaluation, so very deep
+    /// expressions end with an error instead of overflowing the stack
+    ///
+    /// The default suits the main thread. Lower it when evaluating on a thread
+    /// with a small stack, since nested calls take several kilobytes of stack
+    /// each in debug builds.
+    pub fn set_max_depth(&mut self, limit: usize) {
+        self.max_depth = limit;
+    }
+
+    /// Let `var` declare each of the global variables `names` once more,
+    /// assigning it instead of failing with `AlreadyDeclared`, e.g. to run a
+    /// script again in a session that already ran it. A second declaration
+    /// of the same name still fails, as it would in a fresh interpreter.
+    ///
+    /// Returns the names allowed until now, so they can be restored.
+    pub fn set_redeclarable(&mut self, names: HashSet<String>) -> HashSet<String> {
+        std::mem::replace(&mut self.redeclarable, names)
+    }
+
+    /// Register a single-argument function callable from Oak code as `name(x)`
+    ///
+    /// A function registered under the name of a built-in one replaces it,
+    /// whatever the number of arguments the built-in takes, and registering
+    /// the same name again replaces the previous registration. Functions
+    /// defined in a script with `fn` still take precedence over registered ones.
+    pub fn register_function(&mut self, name: &str, f: fn(f64) -> f64) {
+        // Single-argument functions are looked up before the other built-ins
+        self.math_functions.insert(name.to_string(), f);
+    }
+
+    /// Unit of the angles used by the trigonometric functions
+    pub fn angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    /// Switch the trigonometric functions between radians and degrees
+    pub fn set_angle_mode(&mut self, mode: AngleMode) {
+        self.angle_mode = mode;
+    }
+
+    /// Parse and evaluate each expression of `sources` in order, all against
+    /// this interpreter, so variables assigned by one are seen by the next
+    ///
+    /// Returns one result per source. A source that fails to parse gives
+    /// `RuntimeError::InvalidExpression` and does not stop the others.
+    pub fn eval_all(&mut self, sources: &[&str]) -> Vec<Result<Value, RuntimeError>> {
+        sources.iter().map(|source| self.eval_source(source)).collect()
+    }
+
+    /// Global variables assigned so far, by name
+    pub fn variables(&self) -> &HashMap<String, Value> {
+        &self.scopes[0]
+    }
+
+    /// Set a global variable, e.g. to seed the inputs of a script before running it
+    pub fn set_variable(&mut self, name: &str, value: f64) {
+        self.scopes[0].insert(name.to_string(), Value::Number(value));
+    }
+
+    /// Numeric value of a global variable, or `None` if it is not assigned
+    /// or not a number
+    pub fn get_variable(&self, name: &str) -> Option<f64> {
+        self.scopes[0].get(name).and_then(Value::as_number)
+    }
+
+    /// Remove every global variable, keeping functions and constants
+    pub fn clear_variables(&mut self) {
+        self.scopes[0].clear();
+    }
+
+    // Parses a single expression and evaluates it
+    fn eval_source(&mut self, source: &str) -> Result<Value, RuntimeError> {
+        let expr = parse_expr(&tokenize(source)).map_err(|error| RuntimeError::InvalidExpression {
+            expr: source.to_string(),
+            message: error.to_string(),
+        })?;
+        self.evaluate(expr.as_ref())
+    }
+
+    // Evaluates a child node, keeping track of how deeply evaluation is nested
+    fn evaluate(&mut self, node: &dyn Node) -> Result<Value, RuntimeError> {
+        if self.depth >= self.max_depth {
+            return Err(RuntimeError::RecursionLimitExceeded {
+                func: "expression".to_string(),
+                limit: self.max_depth,
+            });
+        }
+        self.depth += 1;
+        let result = node.accept(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Prints a trace line when verbose, formatting it only then
+    fn trace(&self, message: std::fmt::Arguments) {
+        if self.verbose {
+            println!("{}", message);
+        }
+    }
+
+    // Resolves a variable in the innermost scope, then in the global scope.
+    // Functions are defined at the top level, so the scopes of their callers
+    // are never visible to them.
+    fn lookup_variable(&self, name: &str) -> Option<Value> {
+        let innermost = self.scopes.last().and_then(|scope| scope.get(name));
+        innermost.or_else(|| self.scopes[0].get(name)).cloned()
+    }
+
+    // Whether `name` is already a variable of the innermost scope
+    fn is_declared_in_current_scope(&self, name: &str) -> bool {
+        self.scopes.last().is_some_and(|scope| scope.contains_key(name))
+    }
+
+    // Assigns a variable in the innermost scope, which is the global one
+    // outside of function calls
+    fn assign_variable(&mut self, name: &str, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), value);
+        }
+    }
+
+    fn push_scope(&mut self, scope: HashMap<String, Value>) {
+        self.scopes.push(scope);
+    }
+
+    // Drops the innermost scope, never the global one
+    fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    // Evaluates a list of call arguments, each of which must be a number
+    fn eval_numbers(&mut self, func: &str, args: &[Box<dyn Node>]) -> Result<Vec<f64>, RuntimeError> {
+        let values = self.eval_numeric_values(func, args)?;
+        Ok(values.iter().filter_map(Value::as_number).collect())
+    }
+
+    // Evaluates a list of call arguments, each of which must be an integer or a number,
+    // keeping their type
+    fn eval_numeric_values(
+        &mut self,
+        func: &str,
+        args: &[Box<dyn Node>],
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            let value = self.evaluate(arg.as_ref())?;
+            if value.as_number().is_none() {
+                return Err(RuntimeError::TypeMismatch {
+                    op: func.to_string(),
+                });
             }
+            values.push(value);
         }
+        Ok(values)
     }
 
-    fn visit_number(&mut self, node: &Number) -> Value {
-        Value::Number(node.value)
+    // Binds the arguments to the parameters in a new local scope and evaluates the body
+    fn call_user_function(
+        &mut self,
+        node: &FunctionCall,
+        params: &[String],
+        body: &dyn Node,
+    ) -> Result<Value, RuntimeError> {
+        if self.scopes.len() > MAX_CALL_DEPTH {
+            return Err(RuntimeError::RecursionLimitExceeded {
+                func: node.name.clone(),
+                limit: MAX_CALL_DEPTH,
+            });
+        }
+        if node.args.len() != params.len() {
+            return Err(RuntimeError::WrongArity {
+                func: node.name.clone(),
+                expected: params.len(),
+                got: node.args.len(),
+            });
+        }
+        let args = self.eval_numeric_values(&node.name, &node.args)?;
+        let scope = params.iter().cloned().zip(args).collect();
+
+        self.push_scope(scope);
+        let result = self.evaluate(body);
+        self.pop_scope();
+        result
     }
 
-    fn visit_var(&mut self, node: &Var) -> Value {
+    // `calc_architecture("stability", 5, 1, ...)`: the first argument names the
+    // calculation and the remaining ones are collected, in order, as its parameters
+    fn call_calc_architecture(&mut self, node: &FunctionCall) -> Result<Value, RuntimeError> {
+        let Some((first, rest)) = node.args.split_first() else {
+            return Err(RuntimeError::WrongArity {
+                func: node.name.clone(),
+                expected: 1,
+                got: 0,
+            });
+        };
+        let calculation_type = match self.evaluate(first.as_ref())? {
+            Value::String(name) => name,
+            _ => {
+                return Err(RuntimeError::TypeMismatch {
+                    op: node.name.clone(),
+                })
+            }
+        };
+        let params = self.eval_numbers(&node.name, rest)?;
+        Ok(Value::String(calc_architecture_command(&calculation_type, &params)))
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Visitor for Interpreter {
+    fn visit_eval_math_exp(&mut self, node: &EvalMathExp) -> Result<Value, RuntimeError> {
+        self.trace(format_args!("Evaluating math expression: {}", node.expr));
+        // The expression is kept as text, parse it before evaluating it
+        self.eval_source(&node.expr)
+    }
+
+    fn visit_bin_op(&mut self, node: &BinOp) -> Result<Value, RuntimeError> {
+        let left = self.evaluate(node.left.as_ref())?;
+        let right = self.evaluate(node.right.as_ref())?;
+        apply_bin_op(&node.op, &left, &right)
+    }
+
+    fn visit_unary_op(&mut self, node: &UnaryOp) -> Result<Value, RuntimeError> {
+        let operand = self.evaluate(node.operand.as_ref())?;
+        apply_unary_op(&node.op, &operand)
+    }
+
+    fn visit_number(&mut self, node: &Number) -> Result<Value, RuntimeError> {
+        Ok(Value::Number(node.value))
+    }
+
+    fn visit_integer(&mut self, node: &Integer) -> Result<Value, RuntimeError> {
+        Ok(Value::Integer(node.value))
+    }
+
+    fn visit_var(&mut self, node: &Var) -> Result<Value, RuntimeError> {
         // First check if it's a math constant
         if let Some(&constant_value) = self.math_constants.get(&node.name) {
-            println!("Constante matemática '{}' = {}", node.name, constant_value);
-            return Value::Number(constant_value);
+            let value = Value::Number(constant_value);
+            self.trace(format_args!("Math constant '{}' = {}", node.name, value));
+            return Ok(value);
         }
         
         // Then check if it's a variable
-        match self.variables.get(&node.name) {
+        match self.lookup_variable(&node.name) {
             Some(val) => {
-                println!("Variable '{}' = {}", node.name, val);
-                Value::Number(*val)
-            }
-            None => {
-                println!("Variable '{}' no definida", node.name);
-                Value::None
+                self.trace(format_args!("Variable '{}' = {}", node.name, val));
+                Ok(val)
             }
+            None => Err(RuntimeError::UndefinedVariable(node.name.clone())),
         }
     }
 
-    fn visit_assign(&mut self, node: &Assign) -> Value {
-        let val = node.expr.accept(self);
-        if let Value::Number(num) = val {
-            self.variables.insert(node.name.clone(), num);
-            println!("Asignando a '{}' el valor {}", node.name, num);
-            Value::Number(num)
+    // An assignment evaluates to the assigned value, so it can be nested in a
+    // larger expression such as `var y := (var x := 5) + 1`. `var` declares a
+    // variable new to the current scope, a plain `:=` reassigns one of that
+    // scope, so a function body cannot change the globals it reads.
+    fn visit_assign(&mut self, node: &Assign) -> Result<Value, RuntimeError> {
+        let val = self.evaluate(node.expr.as_ref())?;
+        if val.as_number().is_some() {
+            if node.is_declaration
+                && self.is_declared_in_current_scope(&node.name)
+                && !(self.scopes.len() == 1 && self.redeclarable.remove(&node.name))
+            {
+                return Err(RuntimeError::AlreadyDeclared(node.name.clone()));
+            }
+            if !node.is_declaration && !self.is_declared_in_current_scope(&node.name) {
+                return Err(match self.lookup_variable(&node.name) {
+                    Some(_) => RuntimeError::GlobalAssignment(node.name.clone()),
+                    None => RuntimeError::UndeclaredAssignment(node.name.clone()),
+                });
+            }
+            self.assign_variable(&node.name, val.clone());
+            self.trace(format_args!("Assigning {} to '{}'", val, node.name));
+            Ok(val)
         } else {
-            println!("Asignación fallida para '{}'", node.name);
-            Value::None
+            Err(RuntimeError::TypeMismatch {
+                op: ":=".to_string(),
+            })
         }
     }
 
-    fn visit_string_literal(&mut self, node: &StringLiteral) -> Value {
-        println!("Cadena: \"{}\"", node.value);
-        Value::String(node.value.clone())
+    fn visit_string_literal(&mut self, node: &StringLiteral) -> Result<Value, RuntimeError> {
+        self.trace(format_args!("String: \"{}\"", node.value));
+        Ok(Value::String(node.value.clone()))
     }
 
-    fn visit_function_call(&mut self, node: &FunctionCall) -> Value {
-        println!(
-            "Llamada a función '{}', args: {}",
+    fn visit_function_call(&mut self, node: &FunctionCall) -> Result<Value, RuntimeError> {
+        self.trace(format_args!(
+            "Calling function '{}' with {} argument(s)",
             node.name,
             node.args.len()
-        );
+        ));
         
-        // Check if it's a math function
+        // Architectural calculations take a calculation name followed by any number
+        // of numeric parameters
+        if node.name == "calc_architecture" {
+            return self.call_calc_architecture(node);
+        }
+
+        // Functions defined in the script take precedence over the built-in ones
+        if let Some(function) = self.user_functions.get(&node.name) {
+            let params = function.params.clone();
+            let body = Rc::clone(&function.body);
+            return self.call_user_function(node, &params, body.as_ref());
+        }
+
+        // Built-in functions take a fixed number of arguments, checked before any is
+        // evaluated. One registered on this interpreter takes a single argument,
+        // whatever the built-in of the same name takes.
+        let arity = if self.math_functions.contains_key(&node.name) {
+            Some(1)
+        } else {
+            MathModule::function_arity(&node.name)
+        };
+        if let Some(expected) = arity {
+            if node.args.len() != expected {
+                return Err(RuntimeError::WrongArity {
+                    func: node.name.clone(),
+                    expected,
+                    got: node.args.len(),
+                });
+            }
+        }
+
+        // Check if it's a math function, dispatching on its arity
         if let Some(&math_func) = self.math_functions.get(&node.name) {
-            if node.args.len() != 1 {
-                println!("Error: función '{}' requiere exactamente 1 argumento", node.name);
-                return Value::None;
+            let args = self.eval_numbers(&node.name, &node.args)?;
+            let result = Value::Number(self.angle_mode.call_unary(&node.name, math_func, args[0]));
+            self.trace(format_args!("Result of {}: {}", node.name, result));
+            return Ok(result);
+        }
+
+        // `min`, `max`, `sum`, `mean` and `stddev` take any number of arguments
+        if is_aggregate(&node.name) {
+            let values = self.eval_numeric_values(&node.name, &node.args)?;
+            let result = aggregate(&node.name, &values)?;
+            self.trace(format_args!("Result of {}: {}", node.name, result));
+            return Ok(result);
+        }
+
+        if let Some(&math_func) = self.binary_math_functions.get(&node.name) {
+            let args = self.eval_numbers(&node.name, &node.args)?;
+            let result = Value::Number(self.angle_mode.call_binary(&node.name, math_func, args[0], args[1]));
+            self.trace(format_args!("Result of {}: {}", node.name, result));
+            return Ok(result);
+        }
+
+        if let Some(&math_func) = self.ternary_math_functions.get(&node.name) {
+            let args = self.eval_numbers(&node.name, &node.args)?;
+            let result = Value::Number(math_func(args[0], args[1], args[2]));
+            self.trace(format_args!("Result of {}: {}", node.name, result));
+            return Ok(result);
+        }
+
+        Err(RuntimeError::UnknownFunction(node.name.clone()))
+    }
+
+    fn visit_function_def(&mut self, node: &FunctionDef) -> Result<Value, RuntimeError> {
+        self.user_functions.insert(
+            node.name.clone(),
+            UserFunction {
+                params: node.params.clone(),
+                body: Rc::clone(&node.body),
+            },
+        );
+        Ok(Value::None)
+    }
+
+    fn visit_if(&mut self, node: &If) -> Result<Value, RuntimeError> {
+        match self.evaluate(node.cond.as_ref())? {
+            Value::Bool(true) => self.evaluate(node.then_branch.as_ref()),
+            Value::Bool(false) => self.evaluate(node.else_branch.as_ref()),
+            _ => Err(RuntimeError::TypeMismatch { op: "if".to_string() }),
+        }
+    }
+
+    fn visit_while(&mut self, node: &While) -> Result<Value, RuntimeError> {
+        let mut iterations = 0;
+        loop {
+            match self.evaluate(node.cond.as_ref())? {
+                Value::Bool(true) => {}
+                Value::Bool(false) => return Ok(Value::None),
+                _ => return Err(RuntimeError::TypeMismatch { op: "while".to_string() }),
             }
-            
-            let arg = node.args[0].accept(self);
-            if let Value::Number(x) = arg {
-                let result = math_func(x);
-                println!("Resultado de {}: {}", node.name, result);
-                return Value::Number(result);
-            } else {
-                println!("Error: argumento de '{}' debe ser un número", node.name);
-                return Value::None;
+            if iterations == self.max_iterations {
+                return Err(RuntimeError::IterationLimitExceeded {
+                    limit: self.max_iterations,
+                });
             }
+            iterations += 1;
+            self.evaluate(node.body.as_ref())?;
         }
-        
-        // Handle other function calls (existing logic)
-        for arg in &node.args {
-            arg.accept(self);
-        }
-        Value::None
     }
 
-    fn visit_comment(&mut self, node: &Comment) -> Value {
-        println!("Comentario: {}", node.value);
-        Value::None
+    fn visit_comment(&mut self, node: &Comment) -> Result<Value, RuntimeError> {
+        self.trace(format_args!("Comment: {}", node.value));
+        Ok(Value::None)
+    }
+
+    fn visit_discard(&mut self, node: &Discard) -> Result<Value, RuntimeError> {
+        node.statement.accept(self)?;
+        Ok(Value::None)
+    }
+}
+
+/// Whether `name` is one of the variadic functions handled by `aggregate`
+pub(crate) fn is_aggregate(name: &str) -> bool {
+    AGGREGATE_FUNCTIONS.contains(&name)
+}
+
+/// Reduce numeric values with `min`, `max`, `sum`, `mean` or `stddev`
+///
+/// Integers stay integers when every value is one, as with the arithmetic
+/// operators, except for `mean` and `stddev` which always give a float.
+pub(crate) fn aggregate(name: &str, values: &[Value]) -> Result<Value, RuntimeError> {
+    let Some((first, rest)) = values.split_first() else {
+        return Err(RuntimeError::MissingArguments(name.to_string()));
+    };
+    if name == "sum" {
+        return rest
+            .iter()
+            .try_fold(first.clone(), |total, value| apply_bin_op("+", &total, value));
+    }
+    if name == "mean" || name == "stddev" {
+        let numbers: Vec<f64> = values.iter().filter_map(Value::as_number).collect();
+        let result = if name == "mean" { MathModule::mean(&numbers) } else { MathModule::stddev(&numbers) };
+        return Ok(Value::Number(result));
+    }
+
+    let integers: Option<Vec<i64>> = values
+        .iter()
+        .map(|value| match value {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        })
+        .collect();
+    if let Some(integers) = integers {
+        let result = if name == "min" {
+            integers.into_iter().fold(i64::MAX, i64::min)
+        } else {
+            integers.into_iter().fold(i64::MIN, i64::max)
+        };
+        return Ok(Value::Integer(result));
+    }
+
+    let numbers = values.iter().filter_map(Value::as_number);
+    let result = if name == "min" {
+        numbers.fold(f64::INFINITY, MathModule::min)
+    } else {
+        numbers.fold(f64::NEG_INFINITY, MathModule::max)
+    };
+    Ok(Value::Number(result))
+}
+
+/// Apply a prefix `-` or `+` to a value
+///
+/// Integers stay integers, negating `i64::MIN` is an overflow.
+pub(crate) fn apply_unary_op(op: &str, operand: &Value) -> Result<Value, RuntimeError> {
+    match (op, operand) {
+        ("-", Value::Integer(value)) => value
+            .checked_neg()
+            .map(Value::Integer)
+            .ok_or_else(|| RuntimeError::IntegerOverflow { op: op.to_string() }),
+        ("-", Value::Number(value)) => Ok(Value::Number(-value)),
+        ("+", Value::Integer(_) | Value::Number(_)) => Ok(operand.clone()),
+        ("-" | "+", _) => Err(RuntimeError::TypeMismatch { op: op.to_string() }),
+        _ => Err(RuntimeError::UnknownOperator(op.to_string())),
+    }
+}
+
+/// Apply a binary operator to two values
+///
+/// Two integers give an integer; an integer mixed with a float is promoted to a float.
+/// Two strings can only be compared, see `string_bin_op`.
+pub(crate) fn apply_bin_op(op: &str, left: &Value, right: &Value) -> Result<Value, RuntimeError> {
+    match (left, right) {
+        (Value::Integer(l), Value::Integer(r)) => integer_bin_op(op, *l, *r),
+        (Value::String(l), Value::String(r)) => string_bin_op(op, l, r),
+        _ => match (left.as_number(), right.as_number()) {
+            (Some(l), Some(r)) => float_bin_op(op, l, r),
+            _ => Err(RuntimeError::TypeMismatch { op: op.to_string() }),
+        },
+    }
+}
+
+// Applies a binary operator to two floats. As with integers, `/` and `%` fail on
+// a zero divisor, so infinities and NaN only come from explicit math functions.
+fn float_bin_op(op: &str, l: f64, r: f64) -> Result<Value, RuntimeError> {
+    match op {
+        "+" => Ok(Value::Number(l + r)),
+        "-" => Ok(Value::Number(l - r)),
+        "*" => Ok(Value::Number(l * r)),
+        "/" | "%" if r == 0.0 => Err(RuntimeError::DivisionByZero),
+        "/" => Ok(Value::Number(l / r)),
+        // Floating remainder with the sign of the dividend
+        "%" => Ok(Value::Number(l % r)),
+        "^" => Ok(Value::Number(l.powf(r))),
+        // Comparisons tolerate rounding error, see `DEFAULT_EPSILON`. Numbers that
+        // are equal within it are neither smaller nor larger than each other, so
+        // `0.1 + 0.2 <= 0.3` holds like `0.1 + 0.2 == 0.3` does.
+        "==" => Ok(Value::Bool(numbers_equal(l, r))),
+        "!=" => Ok(Value::Bool(!numbers_equal(l, r))),
+        "<" => Ok(Value::Bool(l < r && !numbers_equal(l, r))),
+        "<=" => Ok(Value::Bool(l < r || numbers_equal(l, r))),
+        ">" => Ok(Value::Bool(l > r && !numbers_equal(l, r))),
+        ">=" => Ok(Value::Bool(l > r || numbers_equal(l, r))),
+        _ => Err(RuntimeError::UnknownOperator(op.to_string())),
+    }
+}
+
+// Applies a binary operator to two integers. `/` truncates toward zero and, like `%`,
+// fails on a zero divisor; a negative power falls back to a float.
+fn integer_bin_op(op: &str, l: i64, r: i64) -> Result<Value, RuntimeError> {
+    let result = match op {
+        "+" => l.checked_add(r),
+        "-" => l.checked_sub(r),
+        "*" => l.checked_mul(r),
+        "/" | "%" if r == 0 => return Err(RuntimeError::DivisionByZero),
+        "/" => l.checked_div(r),
+        "%" => l.checked_rem(r),
+        "^" if r < 0 => return Ok(Value::Number((l as f64).powf(r as f64))),
+        "^" => u32::try_from(r).ok().and_then(|exp| l.checked_pow(exp)),
+        "==" => return Ok(Value::Bool(l == r)),
+        "!=" => return Ok(Value::Bool(l != r)),
+        "<" => return Ok(Value::Bool(l < r)),
+        "<=" => return Ok(Value::Bool(l <= r)),
+        ">" => return Ok(Value::Bool(l > r)),
+        ">=" => return Ok(Value::Bool(l >= r)),
+        _ => return Err(RuntimeError::UnknownOperator(op.to_string())),
+    };
+    result.map(Value::Integer).ok_or_else(|| RuntimeError::IntegerOverflow {
+        op: op.to_string(),
+    })
+}
+
+// Compares two strings. `==` and `!=` compare them exactly, and `<`, `<=`, `>` and `>=`
+// order them lexicographically by Unicode code point, so "B" < "a" and "10" < "9".
+// Arithmetic on strings is a type mismatch.
+fn string_bin_op(op: &str, l: &str, r: &str) -> Result<Value, RuntimeError> {
+    match op {
+        "==" => Ok(Value::Bool(l == r)),
+        "!=" => Ok(Value::Bool(l != r)),
+        "<" => Ok(Value::Bool(l < r)),
+        "<=" => Ok(Value::Bool(l <= r)),
+        ">" => Ok(Value::Bool(l > r)),
+        ">=" => Ok(Value::Bool(l >= r)),
+        "+" | "-" | "*" | "/" | "%" | "^" => Err(RuntimeError::TypeMismatch { op: op.to_string() }),
+        _ => Err(RuntimeError::UnknownOperator(op.to_string())),
     }
 }
+
+// Compares two numbers within the interpreter's default equality tolerance
+fn numbers_equal(left: f64, right: f64) -> bool {
+    Value::Number(left).approx_eq(&Value::Number(right), DEFAULT_EPSILON)
+}
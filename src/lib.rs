@@ -7,5 +7,51 @@ pub mod runtime;
 pub mod tests;
 pub mod tokenizer;
 
+use interpreter::{Interpreter, RuntimeError};
+use parser::{parse_expr_str, ScriptError, Value};
+use thiserror::Error;
+use tokenizer::LexError;
+
 // Re-export math module for easy access
-pub use math::{MathModule, get_math_functions, get_math_constants};
+pub use math::{
+    calc_architecture, calc_architecture_batch, calc_architecture_command,
+    format_architectural_result, get_binary_math_functions, get_math_constants, get_math_functions, get_ternary_math_functions,
+    math_constant_names, math_function_names, ArchitecturalResult, MathModule,
+};
+#[cfg(feature = "serde")]
+pub use math::calc_architecture_json;
+
+/// Errors returned by `eval`, from any stage of evaluating an expression
+#[derive(Error, Debug, PartialEq)]
+pub enum OakError {
+    #[error("Lexical error: {0}")]
+    Lex(LexError),
+    #[error("Parse error: {0}")]
+    Parse(String),
+    #[error("Runtime error: {0}")]
+    Runtime(RuntimeError),
+}
+
+impl From<ScriptError> for OakError {
+    fn from(error: ScriptError) -> Self {
+        match error {
+            ScriptError::Lex(error) => OakError::Lex(error),
+            ScriptError::Runtime(error) => OakError::Runtime(error),
+            // Parsing an expression does no I/O, anything else is a parse failure
+            other => OakError::Parse(other.to_string()),
+        }
+    }
+}
+
+/// Evaluate a single expression with a fresh `Interpreter` and return its value
+///
+/// # Example
+/// ```rust
+/// use oak::parser::Value;
+/// assert_eq!(oak::eval("2 + 3 * 4").unwrap(), Value::Number(14.0));
+/// assert!(oak::eval("@").is_err());
+/// ```
+pub fn eval(source: &str) -> Result<Value, OakError> {
+    let expr = parse_expr_str(source)?;
+    expr.accept(&mut Interpreter::new()).map_err(OakError::Runtime)
+}
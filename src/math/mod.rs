@@ -4,17 +4,125 @@ use std::f64::consts::PI;
 /// Mathematical functions for the Oak programming language
 pub struct MathModule;
 
+/// Highest number of floors accepted by the building calculations
+pub const MAX_FLOORS: u32 = 200;
+
+/// Standard strength design load combinations, by name, with the factors applied
+/// to the dead (D), live (L), wind (W) and snow (S) loads, in that order
+pub const LOAD_COMBINATIONS: &[(&str, [f64; 4])] = &[
+    ("1.4D", [1.4, 0.0, 0.0, 0.0]),
+    ("1.2D + 1.6L + 0.5S", [1.2, 1.6, 0.0, 0.5]),
+    ("1.2D + 1.6L", [1.2, 1.6, 0.0, 0.0]),
+    ("1.2D + 1.6S + 1.0L", [1.2, 1.0, 0.0, 1.6]),
+    ("1.2D + 1.6S + 0.5W", [1.2, 0.0, 0.5, 1.6]),
+    ("1.2D + 1.0W + 1.0L + 0.5S", [1.2, 1.0, 1.0, 0.5]),
+    ("1.2D + 1.0W", [1.2, 0.0, 1.0, 0.0]),
+    ("0.9D + 1.0W", [0.9, 0.0, 1.0, 0.0]),
+];
+
 /// Building stability verification result
 ///
 /// - If `overturning_moment` is near zero, `stability_ratio` will be 1e6 ("perfect stability").
 /// - If `overturning_moment` is negative, the function returns an error.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StabilityResult {
     pub resisting_moment: f64,
     pub overturning_moment: f64,
     pub stability_ratio: f64,
     pub is_stable: bool,
     pub safety_margin: f64,
+    /// Stability ratio required for the building to be stable
+    pub safety_factor: f64,
+}
+
+/// Seismic base shear calculation result
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SeismicResult {
+    pub seismic_coefficient: f64,
+    pub base_shear: f64,
+}
+
+/// Simply supported beam deflection result
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BeamDeflectionResult {
+    /// Maximum deflection at midspan (m)
+    pub deflection: f64,
+    /// Span divided by the maximum deflection (L/δ)
+    pub span_ratio: f64,
+}
+
+/// Wind stiffness (plan slenderness) compliance result
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WindStiffnessResult {
+    /// Shorter plan dimension divided by the longer one
+    pub slenderness_ratio: f64,
+    /// Minimum slenderness ratio required for compliance
+    pub min_ratio: f64,
+    pub is_compliant: bool,
+}
+
+/// Height slenderness (vertical aspect ratio) compliance result
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HeightSlendernessResult {
+    /// Height divided by the shorter plan dimension
+    pub slenderness_ratio: f64,
+    /// Maximum slenderness ratio allowed for compliance
+    pub max_ratio: f64,
+    pub is_compliant: bool,
+}
+
+/// Factored load of a load combination
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LoadCombinationResult {
+    /// Name of the combination, as listed in `LOAD_COMBINATIONS`
+    pub combination: String,
+    /// Factored total load, in the unit of the loads given
+    pub factored_load: f64,
+}
+
+/// Division of a length into two segments in the golden ratio
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GoldenPartitionResult {
+    /// Longer segment (m)
+    pub major: f64,
+    /// Shorter segment (m), such that `major / minor` is φ
+    pub minor: f64,
+}
+
+/// Outcome of a named architectural calculation run through `calc_architecture`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ArchitecturalResult {
+    /// Result of a `"stability"` calculation
+    Stability(StabilityResult),
+    /// Result of a `"minimum_dead_load"` calculation, in kN/m²
+    MinimumDeadLoad(f64),
+    /// Result of a `"seismic"` calculation
+    Seismic(SeismicResult),
+    /// Result of a `"beam_deflection"` calculation
+    BeamDeflection(BeamDeflectionResult),
+    /// Result of a `"wind_stiffness"` calculation
+    WindStiffness(WindStiffnessResult),
+    /// Result of a `"wind_pressure"` calculation, in N/m²
+    WindPressure(f64),
+    /// Result of a `"load_combination"` calculation
+    LoadCombination(LoadCombinationResult),
+    /// Result of a `"golden_partition"` calculation
+    GoldenPartition(GoldenPartitionResult),
+    /// Result of a `"footprint_area"` calculation, in m²
+    FootprintArea(f64),
+    /// Result of a `"volume"` calculation, in m³
+    Volume(f64),
+    /// Result of a `"height_slenderness"` calculation
+    HeightSlenderness(HeightSlendernessResult),
 }
 
 impl MathModule {
@@ -43,6 +151,48 @@ impl MathModule {
         }
     }
 
+    /// Calculate the sine of an angle in degrees
+    pub fn sind(degrees: f64) -> f64 {
+        MathModule::sin(MathModule::to_radians(degrees))
+    }
+
+    /// Calculate the cosine of an angle in degrees
+    pub fn cosd(degrees: f64) -> f64 {
+        MathModule::cos(MathModule::to_radians(degrees))
+    }
+
+    /// Calculate the tangent of an angle in degrees
+    /// Returns NaN at odd multiples of 90°, like `tan`
+    pub fn tand(degrees: f64) -> f64 {
+        MathModule::tan(MathModule::to_radians(degrees))
+    }
+
+    /// Calculate the arcsine of a number, in radians
+    /// Returns NaN for inputs outside [-1, 1]
+    pub fn asin(x: f64) -> f64 {
+        if !(-1.0..=1.0).contains(&x) {
+            f64::NAN
+        } else {
+            x.asin()
+        }
+    }
+
+    /// Calculate the arccosine of a number, in radians
+    /// Returns NaN for inputs outside [-1, 1]
+    pub fn acos(x: f64) -> f64 {
+        if !(-1.0..=1.0).contains(&x) {
+            f64::NAN
+        } else {
+            x.acos()
+        }
+    }
+
+    /// Calculate the arctangent of a number, in radians
+    /// Always defined for all real numbers
+    pub fn atan(x: f64) -> f64 {
+        x.atan()
+    }
+
     /// Calculate the square root of a number
     /// Returns NaN for negative numbers
     pub fn sqrt(x: f64) -> f64 {
@@ -63,6 +213,36 @@ impl MathModule {
         }
     }
 
+    /// Calculate the base-10 logarithm of a number
+    /// Returns NaN for non-positive numbers
+    pub fn log10(x: f64) -> f64 {
+        if x <= 0.0 {
+            f64::NAN
+        } else {
+            x.log10()
+        }
+    }
+
+    /// Calculate the base-2 logarithm of a number
+    /// Returns NaN for non-positive numbers
+    pub fn log2(x: f64) -> f64 {
+        if x <= 0.0 {
+            f64::NAN
+        } else {
+            x.log2()
+        }
+    }
+
+    /// Calculate the logarithm of x in an arbitrary base, as log(x) / log(base)
+    /// Returns NaN for non-positive x and for a base that is non-positive or equal to 1
+    pub fn log_base(x: f64, base: f64) -> f64 {
+        if base <= 0.0 || base == 1.0 {
+            f64::NAN
+        } else {
+            MathModule::log(x) / MathModule::log(base)
+        }
+    }
+
     /// Calculate e raised to the power of x
     /// Always defined for all real numbers
     pub fn exp(x: f64) -> f64 {
@@ -75,6 +255,267 @@ impl MathModule {
         x.abs()
     }
 
+    /// Calculate the sign of a number
+    /// Returns -1 for negative values, 1 for positive values and exactly 0 for
+    /// both 0.0 and -0.0. NaN is propagated.
+    pub fn sign(x: f64) -> f64 {
+        if x == 0.0 {
+            0.0
+        } else {
+            x.signum()
+        }
+    }
+
+    /// Round a number down to the nearest integer
+    /// Always defined for all real numbers
+    pub fn floor(x: f64) -> f64 {
+        x.floor()
+    }
+
+    /// Round a number up to the nearest integer
+    /// Always defined for all real numbers
+    pub fn ceil(x: f64) -> f64 {
+        x.ceil()
+    }
+
+    /// Round a number to the nearest integer
+    /// Halfway cases are rounded away from zero, like `f64::round` (2.5 -> 3, -2.5 -> -3)
+    pub fn round(x: f64) -> f64 {
+        x.round()
+    }
+
+    /// Round a number to a given number of decimals
+    /// Halfway cases are rounded away from zero, like `round` (2.5 -> 3)
+    /// 
+    /// # Returns
+    /// * `Ok(f64)` - The rounded value; values too large to carry that many
+    ///   decimals are returned unchanged
+    /// * `Err(String)` if `decimals` is negative
+    pub fn round_to(value: f64, decimals: i32) -> Result<f64, String> {
+        if decimals < 0 {
+            return Err("Number of decimals must not be negative".to_string());
+        }
+        let factor = 10f64.powi(decimals);
+        let scaled = value * factor;
+        if !scaled.is_finite() {
+            return Ok(value);
+        }
+        Ok(scaled.round() / factor)
+    }
+
+    /// Drop the fractional part of a number, rounding towards zero
+    /// Always defined for all real numbers
+    pub fn trunc(x: f64) -> f64 {
+        x.trunc()
+    }
+
+    /// Calculate the factorial n! of a non-negative integer
+    /// Returns NaN for negative or non-integer inputs.
+    /// The result overflows to infinity for n > 170, since 171! exceeds `f64::MAX`.
+    pub fn factorial(n: f64) -> f64 {
+        if n < 0.0 || n.fract() != 0.0 || n.is_nan() {
+            return f64::NAN;
+        }
+        if n > 170.0 {
+            return f64::INFINITY;
+        }
+
+        let mut result = 1.0;
+        let mut factor = 2.0;
+        while factor <= n {
+            result *= factor;
+            factor += 1.0;
+        }
+        result
+    }
+
+    /// Raise x to the power of y
+    /// Follows IEEE rules, e.g. a negative base with a fractional exponent is NaN
+    pub fn pow(x: f64, y: f64) -> f64 {
+        x.powf(y)
+    }
+
+    /// Raise x to the power of y, failing instead of overflowing to infinity
+    ///
+    /// This is opt-in: `pow` and the `^` operator stay lenient and return
+    /// infinity for results too large for an `f64`.
+    ///
+    /// # Returns
+    /// * `Ok(f64)` - x raised to the power of y
+    /// * `Err(String)` if finite inputs give an infinite result, either by
+    ///   overflowing, e.g. `10 ^ 400`, or by dividing by zero, e.g. `0 ^ -1`
+    pub fn pow_checked(x: f64, y: f64) -> Result<f64, String> {
+        let result = x.powf(y);
+        if result.is_infinite() && x.is_finite() && y.is_finite() {
+            // A negative power of zero is 1 / 0, a pole rather than an overflow
+            if x == 0.0 {
+                return Err(format!("{} ^ {} is a division by zero", x, y));
+            }
+            return Err(format!("{} ^ {} overflows", x, y));
+        }
+        Ok(result)
+    }
+
+    /// Return the smaller of two numbers
+    /// If one argument is NaN, the other one is returned
+    pub fn min(a: f64, b: f64) -> f64 {
+        a.min(b)
+    }
+
+    /// Return the larger of two numbers
+    /// If one argument is NaN, the other one is returned
+    pub fn max(a: f64, b: f64) -> f64 {
+        a.max(b)
+    }
+
+    /// Calculate the arithmetic mean of a list of numbers
+    /// Returns NaN for an empty list
+    pub fn mean(values: &[f64]) -> f64 {
+        if values.is_empty() {
+            return f64::NAN;
+        }
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    /// Calculate the population standard deviation of a list of numbers
+    /// Returns NaN for an empty list
+    pub fn stddev(values: &[f64]) -> f64 {
+        let mean = MathModule::mean(values);
+        let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        variance.sqrt()
+    }
+
+    /// Calculate the four-quadrant arctangent of y/x in radians
+    /// Always defined for all real numbers, the result lies in [-PI, PI]
+    pub fn atan2(y: f64, x: f64) -> f64 {
+        y.atan2(x)
+    }
+
+    /// Calculate sqrt(x² + y²) without intermediate overflow or underflow
+    pub fn hypot(x: f64, y: f64) -> f64 {
+        x.hypot(y)
+    }
+
+    /// Calculate the greatest common divisor of two integers with the Euclidean algorithm
+    /// Returns NaN if either argument is not integer-valued.
+    /// The result is never negative, and gcd(0, 0) is 0.
+    pub fn gcd(a: f64, b: f64) -> f64 {
+        if a.fract() != 0.0 || b.fract() != 0.0 || a.is_nan() || b.is_nan() {
+            return f64::NAN;
+        }
+
+        let (mut a, mut b) = (a.abs(), b.abs());
+        while b != 0.0 {
+            let remainder = a % b;
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+
+    /// Calculate the least common multiple of two integers
+    /// Returns NaN if either argument is not integer-valued.
+    /// The result is never negative, and it is 0 when either argument is 0.
+    pub fn lcm(a: f64, b: f64) -> f64 {
+        let divisor = MathModule::gcd(a, b);
+        if divisor.is_nan() {
+            return f64::NAN;
+        }
+        if divisor == 0.0 {
+            return 0.0;
+        }
+        (a / divisor * b).abs()
+    }
+
+    /// Calculate the floating remainder of a / b
+    /// The result has the sign of the dividend a, so mod(-10, 3) is -1 rather than
+    /// the 2 a mathematical modulo would give. Returns NaN if b is 0.
+    pub fn modulo(a: f64, b: f64) -> f64 {
+        a % b
+    }
+
+    /// Express `part` as a percentage of `whole`, as part / whole * 100
+    ///
+    /// # Returns
+    /// * `Ok(f64)` - The percentage, e.g. 12.5 for 25 out of 200
+    /// * `Err(String)` if `whole` is 0
+    pub fn percent_of(part: f64, whole: f64) -> Result<f64, String> {
+        if whole == 0.0 {
+            return Err("Cannot take a percentage of a zero whole".to_string());
+        }
+        Ok(part / whole * 100.0)
+    }
+
+    /// Increase `base` by `pct` percent, as base * (1 + pct / 100)
+    /// Negative percentages decrease it, so apply_percent(100, -10) is 90
+    pub fn apply_percent(base: f64, pct: f64) -> f64 {
+        // Adding the increase keeps round results exact, 1 + 10 / 100 is not 1.1
+        base + base * pct / 100.0
+    }
+
+    /// Convert a percentage to basis points (1% = 100 bp)
+    pub fn percent_to_bps(pct: f64) -> f64 {
+        pct * 100.0
+    }
+
+    /// Convert basis points to a percentage (100 bp = 1%)
+    pub fn bps_to_percent(bps: f64) -> f64 {
+        bps / 100.0
+    }
+
+    /// Bound x to the range [lo, hi]
+    /// Returns lo if x < lo, hi if x > hi and x otherwise.
+    /// Returns NaN if lo > hi, where the range is empty.
+    pub fn clamp(x: f64, lo: f64, hi: f64) -> f64 {
+        if lo > hi {
+            f64::NAN
+        } else if x < lo {
+            lo
+        } else if x > hi {
+            hi
+        } else {
+            x
+        }
+    }
+
+    /// Number of arguments taken by the built-in function `name`
+    ///
+    /// # Returns
+    /// * `Some(n)` - For the functions of the one, two and three-argument registries
+    /// * `None` for unknown names and for the functions of `AGGREGATE_FUNCTIONS`,
+    ///   which take any number of arguments
+    pub fn function_arity(name: &str) -> Option<usize> {
+        static ARITIES: std::sync::OnceLock<std::collections::HashMap<String, usize>> =
+            std::sync::OnceLock::new();
+
+        if AGGREGATE_FUNCTIONS.contains(&name) {
+            return None;
+        }
+        let arities = ARITIES.get_or_init(|| {
+            // Inserted from the most to the fewest arguments, so a name found in several
+            // registries gets the arity it is dispatched with, the smallest one
+            get_ternary_math_functions()
+                .into_keys()
+                .map(|name| (name, 3))
+                .chain(get_binary_math_functions().into_keys().map(|name| (name, 2)))
+                .chain(get_math_functions().into_keys().map(|name| (name, 1)))
+                .collect()
+        });
+        arities.get(name).copied()
+    }
+
+    /// Divide a by b, returning `fallback` instead of failing or overflowing
+    /// Returns fallback if b is 0, and also if the quotient is not a finite number,
+    /// e.g. when it overflows. The result is never infinite unless fallback is.
+    pub fn safe_divide(a: f64, b: f64, fallback: f64) -> f64 {
+        let quotient = a / b;
+        if b == 0.0 || !quotient.is_finite() {
+            fallback
+        } else {
+            quotient
+        }
+    }
+
     /// Convert degrees to radians
     /// Always defined for all real numbers
     pub fn to_radians(degrees: f64) -> f64 {
@@ -97,6 +538,26 @@ impl MathModule {
         std::f64::consts::E
     }
 
+    /// Get the value of tau, a full turn in radians (2π)
+    pub fn tau() -> f64 {
+        std::f64::consts::TAU
+    }
+
+    /// Get the value of the square root of 2
+    pub fn sqrt_2() -> f64 {
+        std::f64::consts::SQRT_2
+    }
+
+    /// Get the value of the natural logarithm of 2
+    pub fn ln_2() -> f64 {
+        std::f64::consts::LN_2
+    }
+
+    /// Get the value of the golden ratio φ, (1 + √5) / 2
+    pub fn phi() -> f64 {
+        (1.0 + 5f64.sqrt()) / 2.0
+    }
+
     /// Check if a value is NaN (Not a Number)
     pub fn is_nan(x: f64) -> bool {
         x.is_nan()
@@ -116,6 +577,11 @@ impl MathModule {
 
     /// Validate building dimension parameters
     /// 
+    /// The number of floors is capped at `MAX_FLOORS`, above the tallest
+    /// buildings ever built, so absurd inputs are rejected instead of silently
+    /// producing numbers. This is separate from the guard converting floor
+    /// counts received as numbers, which only protects against `u32` overflow.
+    /// 
     /// # Arguments
     /// * `building_length_a` - Length of windward face (m)
     /// * `building_width_b` - Width perpendicular to wind (m)
@@ -143,6 +609,9 @@ impl MathModule {
         if num_floors == 0 {
             return Err("Number of floors must be at least 1".to_string());
         }
+        if num_floors > MAX_FLOORS {
+            return Err("Number of floors exceeds realistic maximum".to_string());
+        }
 
         // Check for extremely small buildings that might cause numerical issues
         if building_length_a < 0.1 || building_width_b < 0.1 {
@@ -197,8 +666,26 @@ impl MathModule {
         Ok(())
     }
 
+    /// Validate that a calculation input is positive and finite
+    /// 
+    /// # Arguments
+    /// * `value` - The input value to validate
+    /// * `parameter_name` - Name of the parameter for error messages
+    /// 
+    /// # Returns
+    /// * `Ok(())` if the value is valid
+    /// * `Err(String)` with error message if validation fails
+    fn validate_positive_parameter(value: f64, parameter_name: &str) -> Result<(), String> {
+        if !value.is_finite() || value <= 0.0 {
+            return Err(format!("{} must be positive and finite", parameter_name));
+        }
+        Ok(())
+    }
+
     /// Calculate center to corner distance (diagonal distance from center to corner)
     /// 
+    /// Assumes the center of gravity is at the geometric center of the plan.
+    /// 
     /// # Arguments
     /// * `building_length_a` - Length of windward face (m)
     /// * `building_width_b` - Width perpendicular to wind (m)
@@ -210,8 +697,48 @@ impl MathModule {
         building_length_a: f64,
         building_width_b: f64,
     ) -> Result<f64, String> {
+        MathModule::calculate_center_to_corner_distance_with_offset(building_length_a, building_width_b, 0.0, 0.0)
+    }
+
+    /// Calculate the distance from an off-center center of gravity to the furthest corner
+    /// 
+    /// Asymmetric buildings have their center of gravity away from the geometric
+    /// center, which lengthens the arm to the furthest corner.
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of windward face (m)
+    /// * `building_width_b` - Width perpendicular to wind (m)
+    /// * `eccentricity_x` - Offset of the center of gravity along the length (m)
+    /// * `eccentricity_y` - Offset of the center of gravity along the width (m)
+    /// 
+    /// # Returns
+    /// * `Ok(f64)` - The distance to the furthest corner
+    /// * `Err(String)` with error message if the offset places the center of gravity
+    ///   outside the footprint or the calculation fails
+    /// 
+    /// # Example
+    /// ```rust
+    /// use oak::MathModule;
+    /// let distance = MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, 1.0, 1.0).unwrap();
+    /// assert!((distance - 4.0f64.hypot(5.0)).abs() < 1e-12);
+    /// ```
+    pub fn calculate_center_to_corner_distance_with_offset(
+        building_length_a: f64,
+        building_width_b: f64,
+        eccentricity_x: f64,
+        eccentricity_y: f64,
+    ) -> Result<f64, String> {
+        if !eccentricity_x.is_finite() || !eccentricity_y.is_finite() {
+            return Err("Center of gravity offset must be finite".to_string());
+        }
+        if eccentricity_x.abs() > building_length_a / 2.0 || eccentricity_y.abs() > building_width_b / 2.0 {
+            return Err("Center of gravity offset places it outside the building footprint".to_string());
+        }
+
+        // The furthest corner is the one opposite to the offset
         let center_to_corner_distance = MathModule::sqrt(
-            (building_length_a / 2.0).powi(2) + (building_width_b / 2.0).powi(2)
+            (building_length_a / 2.0 + eccentricity_x.abs()).powi(2)
+                + (building_width_b / 2.0 + eccentricity_y.abs()).powi(2)
         );
 
         // Check for invalid center to corner distance
@@ -265,11 +792,76 @@ impl MathModule {
         building_height: f64,
         num_floors: u32,
         wind_force_height: f64,
+    ) -> Result<StabilityResult, String> {
+        MathModule::verify_building_stability_with_factor(
+            dead_load_per_sqm,
+            wind_load_per_sqm,
+            building_length_a,
+            building_width_b,
+            building_height,
+            num_floors,
+            wind_force_height,
+            3.0,
+        )
+    }
+
+    /// Verify building stability with the wind resultant acting at mid-height
+    /// 
+    /// Same as `verify_building_stability` with `wind_force_height` set to
+    /// `building_height / 2.0`. The dimensions are validated first, so an invalid
+    /// height is reported as such rather than as an invalid wind force height.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use oak::MathModule;
+    /// let result = MathModule::verify_building_stability_default_height(5.0, 1.0, 20.0, 15.0, 30.0, 10);
+    /// assert!(result.unwrap().is_stable);
+    /// ```
+    pub fn verify_building_stability_default_height(
+        dead_load_per_sqm: f64,
+        wind_load_per_sqm: f64,
+        building_length_a: f64,
+        building_width_b: f64,
+        building_height: f64,
+        num_floors: u32,
+    ) -> Result<StabilityResult, String> {
+        MathModule::validate_building_parameters(building_length_a, building_width_b, building_height, num_floors)?;
+
+        MathModule::verify_building_stability(
+            dead_load_per_sqm,
+            wind_load_per_sqm,
+            building_length_a,
+            building_width_b,
+            building_height,
+            num_floors,
+            building_height / 2.0,
+        )
+    }
+
+    /// Verify building stability against overturning due to wind loads with a custom safety factor
+    /// 
+    /// # Arguments
+    /// Same as `verify_building_stability`, plus:
+    /// * `safety_factor` - Minimum Me/Mv ratio for the building to be stable
+    /// 
+    /// # Returns
+    /// * `StabilityResult` with `is_stable` and `safety_margin` computed against `safety_factor`
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_building_stability_with_factor(
+        dead_load_per_sqm: f64,
+        wind_load_per_sqm: f64,
+        building_length_a: f64,
+        building_width_b: f64,
+        building_height: f64,
+        num_floors: u32,
+        wind_force_height: f64,
+        safety_factor: f64,
     ) -> Result<StabilityResult, String> {
         // Validate input parameters
         if dead_load_per_sqm <= 0.0 {
             return Err("Dead load per square meter must be positive".to_string());
         }
+        MathModule::validate_positive_parameter(safety_factor, "Safety factor")?;
         MathModule::validate_building_parameters(building_length_a, building_width_b, building_height, num_floors)?;
         MathModule::validate_wind_parameters(wind_load_per_sqm, wind_force_height, building_height)?;
 
@@ -307,9 +899,9 @@ impl MathModule {
             return Err("Negative overturning moment is physically impossible".to_string());
         };
 
-        // Check stability criterion (Me/Mv >= 3)
-        let is_stable = stability_ratio >= 3.0;
-        let safety_margin = stability_ratio - 3.0;
+        // Check stability criterion (Me/Mv >= safety factor)
+        let is_stable = stability_ratio >= safety_factor;
+        let safety_margin = stability_ratio - safety_factor;
 
         // Final validation of result values
         MathModule::validate_calculation_result(safety_margin, "Safety margin calculation")?;
@@ -320,6 +912,7 @@ impl MathModule {
             stability_ratio,
             is_stable,
             safety_margin,
+            safety_factor,
         })
     }
 
@@ -387,6 +980,671 @@ impl MathModule {
         
         Ok(required_dead_load_per_sqm)
     }
+
+    /// Calculate the seismic base shear of a building (V = Cs * W)
+    /// 
+    /// The seismic response coefficient is derived as Cs = Z * I / R.
+    /// 
+    /// # Arguments
+    /// * `zone_factor` - Seismic zone factor Z
+    /// * `importance_factor` - Importance factor I
+    /// * `response_modification_factor` - Response modification factor R
+    /// * `total_weight` - Total seismic weight of the building W (kN)
+    /// 
+    /// # Returns
+    /// * `Ok(SeismicResult)` with the seismic coefficient and base shear (kN)
+    /// * `Err(String)` with error message if validation fails
+    /// 
+    /// # Example
+    /// ```rust
+    /// use oak::MathModule;
+    /// let result = MathModule::calculate_seismic_base_shear(0.4, 1.0, 8.0, 10000.0).unwrap();
+    /// assert!((result.seismic_coefficient - 0.05).abs() < 1e-12);
+    /// assert!((result.base_shear - 500.0).abs() < 1e-9);
+    /// ```
+    pub fn calculate_seismic_base_shear(
+        zone_factor: f64,
+        importance_factor: f64,
+        response_modification_factor: f64,
+        total_weight: f64,
+    ) -> Result<SeismicResult, String> {
+        // Validate input parameters
+        MathModule::validate_positive_parameter(zone_factor, "Zone factor")?;
+        MathModule::validate_positive_parameter(importance_factor, "Importance factor")?;
+        MathModule::validate_positive_parameter(response_modification_factor, "Response modification factor")?;
+        MathModule::validate_positive_parameter(total_weight, "Total weight")?;
+
+        // Calculate seismic response coefficient Cs = Z * I / R
+        let seismic_coefficient = zone_factor * importance_factor / response_modification_factor;
+        MathModule::validate_calculation_result(seismic_coefficient, "Seismic coefficient calculation")?;
+
+        // Calculate base shear V = Cs * W
+        let base_shear = seismic_coefficient * total_weight;
+        MathModule::validate_calculation_result(base_shear, "Base shear calculation")?;
+
+        Ok(SeismicResult {
+            seismic_coefficient,
+            base_shear,
+        })
+    }
+
+    /// Calculate the maximum deflection of a simply supported beam under uniform load
+    /// 
+    /// Uses δ = 5wL⁴ / (384EI).
+    /// 
+    /// # Arguments
+    /// * `distributed_load` - Uniformly distributed load w (kN/m)
+    /// * `span` - Beam span L (m)
+    /// * `elastic_modulus` - Modulus of elasticity E (kPa)
+    /// * `moment_of_inertia` - Second moment of area I (m⁴)
+    /// 
+    /// # Returns
+    /// * `Ok(BeamDeflectionResult)` with the deflection (m) and the span/deflection ratio
+    /// * `Err(String)` with error message if validation fails
+    pub fn calculate_beam_deflection(
+        distributed_load: f64,
+        span: f64,
+        elastic_modulus: f64,
+        moment_of_inertia: f64,
+    ) -> Result<BeamDeflectionResult, String> {
+        // Validate input parameters
+        MathModule::validate_positive_parameter(distributed_load, "Distributed load")?;
+        MathModule::validate_positive_parameter(span, "Span")?;
+        MathModule::validate_positive_parameter(elastic_modulus, "Elastic modulus")?;
+        MathModule::validate_positive_parameter(moment_of_inertia, "Moment of inertia")?;
+
+        // Calculate flexural stiffness EI
+        let flexural_stiffness = elastic_modulus * moment_of_inertia;
+        MathModule::validate_calculation_result(flexural_stiffness, "Flexural stiffness calculation")?;
+
+        // Calculate maximum deflection δ = 5wL⁴ / (384EI)
+        let deflection = 5.0 * distributed_load * span.powi(4) / (384.0 * flexural_stiffness);
+        MathModule::validate_calculation_result(deflection, "Deflection calculation")?;
+
+        // Calculate span/deflection ratio L/δ
+        let span_ratio = span / deflection;
+        MathModule::validate_calculation_result(span_ratio, "Span ratio calculation")?;
+
+        Ok(BeamDeflectionResult {
+            deflection,
+            span_ratio,
+        })
+    }
+
+    /// Calculate the factored total load of a named load combination
+    /// 
+    /// The combination is one of the names in `LOAD_COMBINATIONS`, e.g. `"1.2D + 1.6L"`.
+    /// Spaces and letter case are ignored when matching it.
+    /// 
+    /// # Arguments
+    /// * `dead` - Dead load D
+    /// * `live` - Live load L
+    /// * `wind` - Wind load W
+    /// * `snow` - Snow load S
+    /// * `combo` - Name of the load combination
+    /// 
+    /// # Returns
+    /// * `Ok(f64)` with the factored total load, in the unit of the loads given
+    /// * `Err(String)` if a load is negative or not finite, or the combination is unknown
+    /// 
+    /// # Example
+    /// ```rust
+    /// use oak::MathModule;
+    /// let total = MathModule::calculate_load_combination(5.0, 3.0, 1.0, 0.0, "1.2D + 1.6L").unwrap();
+    /// assert!((total - 10.8).abs() < 1e-9);
+    /// ```
+    pub fn calculate_load_combination(
+        dead: f64,
+        live: f64,
+        wind: f64,
+        snow: f64,
+        combo: &str,
+    ) -> Result<f64, String> {
+        // Validate input parameters, a load may be absent
+        for (load, name) in [(dead, "Dead load"), (live, "Live load"), (wind, "Wind load"), (snow, "Snow load")] {
+            if !load.is_finite() || load < 0.0 {
+                return Err(format!("{} must be non-negative and finite", name));
+            }
+        }
+
+        let factors = MathModule::load_combination_factors(combo)
+            .ok_or_else(|| format!("Unknown load combination '{}'", combo))?;
+
+        let total = factors[0] * dead + factors[1] * live + factors[2] * wind + factors[3] * snow;
+        MathModule::validate_calculation_result(total, "Load combination calculation")?;
+
+        Ok(total)
+    }
+
+    // Looks up the factors of a load combination, ignoring spaces and case
+    fn load_combination_factors(combo: &str) -> Option<[f64; 4]> {
+        let normalize = |name: &str| -> String {
+            name.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| c.to_ascii_uppercase())
+                .collect()
+        };
+        let combo = normalize(combo);
+        LOAD_COMBINATIONS
+            .iter()
+            .find(|(name, _)| normalize(name) == combo)
+            .map(|(_, factors)| *factors)
+    }
+
+    /// Calculate the wind pressure on a surface from the basic wind speed
+    /// 
+    /// Uses q = 0.5 * ρ * v² * Cp.
+    /// 
+    /// # Arguments
+    /// * `wind_speed_ms` - Basic wind speed v (m/s)
+    /// * `air_density` - Air density ρ (kg/m³), about 1.25 at sea level
+    /// * `pressure_coefficient` - Pressure coefficient Cp of the surface
+    /// 
+    /// # Returns
+    /// * `Ok(f64)` with the wind pressure (N/m²); divide by 1000 for the kN/m²
+    ///   expected as `wind_load_per_sqm`
+    /// * `Err(String)` with error message if validation fails
+    /// 
+    /// # Example
+    /// ```rust
+    /// use oak::MathModule;
+    /// let pressure = MathModule::calculate_wind_pressure(40.0, 1.25, 0.8).unwrap();
+    /// assert!((pressure - 800.0).abs() < 1e-9);
+    /// ```
+    pub fn calculate_wind_pressure(
+        wind_speed_ms: f64,
+        air_density: f64,
+        pressure_coefficient: f64,
+    ) -> Result<f64, String> {
+        // Validate input parameters
+        MathModule::validate_positive_parameter(wind_speed_ms, "Wind speed")?;
+        MathModule::validate_positive_parameter(air_density, "Air density")?;
+        MathModule::validate_positive_parameter(pressure_coefficient, "Pressure coefficient")?;
+
+        // Calculate wind pressure q = 0.5 * ρ * v² * Cp
+        let pressure = 0.5 * air_density * wind_speed_ms.powi(2) * pressure_coefficient;
+        MathModule::validate_calculation_result(pressure, "Wind pressure calculation")?;
+
+        Ok(pressure)
+    }
+
+    /// Check that the building plan is stiff enough against wind
+    /// 
+    /// The plan is compliant when the shorter side is at least 1/5 of the longer one.
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of windward face (m)
+    /// * `building_width_b` - Width perpendicular to wind (m)
+    /// 
+    /// # Returns
+    /// * `Ok(WindStiffnessResult)` with the slenderness ratio and compliance
+    /// * `Err(String)` with error message if validation fails
+    pub fn check_wind_stiffness_compliance(
+        building_length_a: f64,
+        building_width_b: f64,
+    ) -> Result<WindStiffnessResult, String> {
+        MathModule::check_wind_stiffness_compliance_with_limit(building_length_a, building_width_b, 0.2)
+    }
+
+    /// Check that the building plan is stiff enough against wind, with a custom limit
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of windward face (m)
+    /// * `building_width_b` - Width perpendicular to wind (m)
+    /// * `min_ratio` - Minimum ratio of shorter to longer side, in (0, 1)
+    /// 
+    /// # Returns
+    /// * `Ok(WindStiffnessResult)` with the slenderness ratio and compliance
+    /// * `Err(String)` with error message if validation fails
+    pub fn check_wind_stiffness_compliance_with_limit(
+        building_length_a: f64,
+        building_width_b: f64,
+        min_ratio: f64,
+    ) -> Result<WindStiffnessResult, String> {
+        // Validate input parameters
+        MathModule::validate_positive_parameter(building_length_a, "Building length")?;
+        MathModule::validate_positive_parameter(building_width_b, "Building width")?;
+        if !(min_ratio > 0.0 && min_ratio < 1.0) {
+            return Err("Minimum slenderness ratio must be between 0 and 1".to_string());
+        }
+
+        // Ratio of the shorter side to the longer side
+        let slenderness_ratio = building_length_a.min(building_width_b) / building_length_a.max(building_width_b);
+        MathModule::validate_calculation_result(slenderness_ratio, "Slenderness ratio calculation")?;
+
+        Ok(WindStiffnessResult {
+            slenderness_ratio,
+            min_ratio,
+            is_compliant: slenderness_ratio >= min_ratio,
+        })
+    }
+
+    /// Divide a length into two segments in the golden ratio
+    /// 
+    /// # Arguments
+    /// * `total_length` - Length to divide (m)
+    /// 
+    /// # Returns
+    /// * `Ok((a, b))` - The longer and shorter segments, with `a + b = total_length`
+    ///   and `a / b = φ`
+    /// * `Err(String)` with error message if validation fails
+    /// 
+    /// # Example
+    /// ```rust
+    /// use oak::MathModule;
+    /// let (a, b) = MathModule::golden_ratio_partition(10.0).unwrap();
+    /// assert!((a + b - 10.0).abs() < 1e-12);
+    /// assert!((a / b - MathModule::phi()).abs() < 1e-10);
+    /// ```
+    pub fn golden_ratio_partition(total_length: f64) -> Result<(f64, f64), String> {
+        MathModule::validate_positive_parameter(total_length, "Total length")?;
+
+        // a = total / φ, and the rest b = total / φ² since 1/φ + 1/φ² = 1
+        let major = total_length / MathModule::phi();
+        let minor = total_length - major;
+        MathModule::validate_calculation_result(major, "Golden partition calculation")?;
+
+        Ok((major, minor))
+    }
+
+    /// Calculate the footprint area of a rectangular building
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of the building (m)
+    /// * `building_width_b` - Width of the building (m)
+    /// 
+    /// # Returns
+    /// * `Ok(f64)` - The footprint area (m²)
+    /// * `Err(String)` with error message if validation fails
+    pub fn building_footprint_area(building_length_a: f64, building_width_b: f64) -> Result<f64, String> {
+        MathModule::validate_positive_parameter(building_length_a, "Building length")?;
+        MathModule::validate_positive_parameter(building_width_b, "Building width")?;
+
+        let area = building_length_a * building_width_b;
+        MathModule::validate_calculation_result(area, "Footprint area calculation")?;
+
+        Ok(area)
+    }
+
+    /// Calculate the volume of a rectangular building
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of the building (m)
+    /// * `building_width_b` - Width of the building (m)
+    /// * `building_height` - Height of the building (m)
+    /// 
+    /// # Returns
+    /// * `Ok(f64)` - The building volume (m³)
+    /// * `Err(String)` with error message if validation fails
+    pub fn building_volume(
+        building_length_a: f64,
+        building_width_b: f64,
+        building_height: f64,
+    ) -> Result<f64, String> {
+        MathModule::validate_positive_parameter(building_height, "Building height")?;
+        let area = MathModule::building_footprint_area(building_length_a, building_width_b)?;
+
+        let volume = area * building_height;
+        MathModule::validate_calculation_result(volume, "Volume calculation")?;
+
+        Ok(volume)
+    }
+
+    /// Calculate the height slenderness of a building, its height divided by
+    /// the shorter plan dimension
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of the building (m)
+    /// * `building_width_b` - Width of the building (m)
+    /// * `building_height` - Height of the building (m)
+    /// 
+    /// # Returns
+    /// * `Ok(f64)` - The height slenderness ratio
+    /// * `Err(String)` with error message if validation fails
+    pub fn calculate_height_slenderness(
+        building_length_a: f64,
+        building_width_b: f64,
+        building_height: f64,
+    ) -> Result<f64, String> {
+        MathModule::validate_positive_parameter(building_length_a, "Building length")?;
+        MathModule::validate_positive_parameter(building_width_b, "Building width")?;
+        MathModule::validate_positive_parameter(building_height, "Building height")?;
+
+        let slenderness_ratio = building_height / building_length_a.min(building_width_b);
+        MathModule::validate_calculation_result(slenderness_ratio, "Height slenderness calculation")?;
+
+        Ok(slenderness_ratio)
+    }
+
+    /// Check that a building is not too slender for its height
+    /// 
+    /// The building is compliant when its height is at most 7 times its
+    /// shorter plan dimension.
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of the building (m)
+    /// * `building_width_b` - Width of the building (m)
+    /// * `building_height` - Height of the building (m)
+    /// 
+    /// # Returns
+    /// * `Ok(HeightSlendernessResult)` with the slenderness ratio and compliance
+    /// * `Err(String)` with error message if validation fails
+    pub fn check_height_slenderness_compliance(
+        building_length_a: f64,
+        building_width_b: f64,
+        building_height: f64,
+    ) -> Result<HeightSlendernessResult, String> {
+        MathModule::check_height_slenderness_compliance_with_limit(
+            building_length_a,
+            building_width_b,
+            building_height,
+            7.0,
+        )
+    }
+
+    /// Check that a building is not too slender for its height, with a custom limit
+    /// 
+    /// # Arguments
+    /// * `building_length_a` - Length of the building (m)
+    /// * `building_width_b` - Width of the building (m)
+    /// * `building_height` - Height of the building (m)
+    /// * `max_ratio` - Maximum ratio of height to shorter plan dimension
+    /// 
+    /// # Returns
+    /// * `Ok(HeightSlendernessResult)` with the slenderness ratio and compliance
+    /// * `Err(String)` with error message if validation fails
+    pub fn check_height_slenderness_compliance_with_limit(
+        building_length_a: f64,
+        building_width_b: f64,
+        building_height: f64,
+        max_ratio: f64,
+    ) -> Result<HeightSlendernessResult, String> {
+        MathModule::validate_positive_parameter(max_ratio, "Maximum slenderness ratio")?;
+        let slenderness_ratio =
+            MathModule::calculate_height_slenderness(building_length_a, building_width_b, building_height)?;
+
+        Ok(HeightSlendernessResult {
+            slenderness_ratio,
+            max_ratio,
+            is_compliant: slenderness_ratio <= max_ratio,
+        })
+    }
+}
+
+/// Run an architectural calculation by name
+///
+/// # Arguments
+/// * `calculation_type` - Name of the calculation to run
+/// * `params` - Numeric parameters, in the same order as the underlying `MathModule` function
+///
+/// # Calculation types
+/// * `"stability"` - dead load, wind load, length a, width b, height, floors, wind force height,
+///   optional safety factor (default 3.0)
+/// * `"minimum_dead_load"` - wind load, length a, width b, height, floors, wind force height, safety factor
+/// * `"seismic"` - zone factor, importance factor, response modification factor, total weight
+/// * `"beam_deflection"` - distributed load, span, elastic modulus, moment of inertia
+/// * `"wind_stiffness"` - length a, width b, optional minimum slenderness ratio (default 0.2)
+/// * `"wind_pressure"` - wind speed, air density, pressure coefficient
+/// * `"load_combination"` - dead, live, wind and snow loads, number of the combination
+///   in `LOAD_COMBINATIONS`, starting at 1
+/// * `"golden_partition"` - total length
+/// * `"footprint_area"` - length a, width b
+/// * `"volume"` - length a, width b, height
+/// * `"height_slenderness"` - length a, width b, height, optional maximum slenderness
+///   ratio (default 7.0)
+///
+/// # Returns
+/// * `Ok(ArchitecturalResult)` with the typed result of the calculation
+/// * `Err(String)` if the calculation type is unknown, the parameter count is wrong
+///   or the calculation itself fails
+pub fn calc_architecture(calculation_type: &str, params: &[f64]) -> Result<ArchitecturalResult, String> {
+    match calculation_type {
+        "stability" => {
+            check_param_count_range(calculation_type, params, 7, 8)?;
+            let result = MathModule::verify_building_stability_with_factor(
+                params[0],
+                params[1],
+                params[2],
+                params[3],
+                params[4],
+                floor_count_from_param(params[5])?,
+                params[6],
+                params.get(7).copied().unwrap_or(3.0),
+            )?;
+            Ok(ArchitecturalResult::Stability(result))
+        }
+        "minimum_dead_load" => {
+            check_param_count(calculation_type, params, 7)?;
+            let result = MathModule::calculate_minimum_dead_load(
+                params[0],
+                params[1],
+                params[2],
+                params[3],
+                floor_count_from_param(params[4])?,
+                params[5],
+                params[6],
+            )?;
+            Ok(ArchitecturalResult::MinimumDeadLoad(result))
+        }
+        "seismic" => {
+            check_param_count(calculation_type, params, 4)?;
+            let result = MathModule::calculate_seismic_base_shear(params[0], params[1], params[2], params[3])?;
+            Ok(ArchitecturalResult::Seismic(result))
+        }
+        "beam_deflection" => {
+            check_param_count(calculation_type, params, 4)?;
+            let result = MathModule::calculate_beam_deflection(params[0], params[1], params[2], params[3])?;
+            Ok(ArchitecturalResult::BeamDeflection(result))
+        }
+        "wind_stiffness" => {
+            check_param_count_range(calculation_type, params, 2, 3)?;
+            let result = match params.get(2) {
+                Some(&min_ratio) => {
+                    MathModule::check_wind_stiffness_compliance_with_limit(params[0], params[1], min_ratio)?
+                }
+                None => MathModule::check_wind_stiffness_compliance(params[0], params[1])?,
+            };
+            Ok(ArchitecturalResult::WindStiffness(result))
+        }
+        "wind_pressure" => {
+            check_param_count(calculation_type, params, 3)?;
+            let result = MathModule::calculate_wind_pressure(params[0], params[1], params[2])?;
+            Ok(ArchitecturalResult::WindPressure(result))
+        }
+        "load_combination" => {
+            check_param_count(calculation_type, params, 5)?;
+            let combination = load_combination_from_param(params[4])?;
+            let factored_load =
+                MathModule::calculate_load_combination(params[0], params[1], params[2], params[3], combination)?;
+            Ok(ArchitecturalResult::LoadCombination(LoadCombinationResult {
+                combination: combination.to_string(),
+                factored_load,
+            }))
+        }
+        "golden_partition" => {
+            check_param_count(calculation_type, params, 1)?;
+            let (major, minor) = MathModule::golden_ratio_partition(params[0])?;
+            Ok(ArchitecturalResult::GoldenPartition(GoldenPartitionResult { major, minor }))
+        }
+        "footprint_area" => {
+            check_param_count(calculation_type, params, 2)?;
+            let area = MathModule::building_footprint_area(params[0], params[1])?;
+            Ok(ArchitecturalResult::FootprintArea(area))
+        }
+        "volume" => {
+            check_param_count(calculation_type, params, 3)?;
+            let volume = MathModule::building_volume(params[0], params[1], params[2])?;
+            Ok(ArchitecturalResult::Volume(volume))
+        }
+        "height_slenderness" => {
+            check_param_count_range(calculation_type, params, 3, 4)?;
+            let result = match params.get(3) {
+                Some(&max_ratio) => MathModule::check_height_slenderness_compliance_with_limit(
+                    params[0], params[1], params[2], max_ratio,
+                )?,
+                None => MathModule::check_height_slenderness_compliance(params[0], params[1], params[2])?,
+            };
+            Ok(ArchitecturalResult::HeightSlenderness(result))
+        }
+        _ => Err(format!("Unknown architectural calculation '{}'", calculation_type)),
+    }
+}
+
+/// Run an architectural calculation by name and format the outcome for display
+///
+/// Failures are not propagated but reported in the returned text, prefixed with `Error:`.
+pub fn calc_architecture_command(calculation_type: &str, params: &[f64]) -> String {
+    match calc_architecture(calculation_type, params) {
+        Ok(result) => format_architectural_result(&result),
+        Err(error) => format!("Error: {}", error),
+    }
+}
+
+/// Format the result of an architectural calculation for display
+pub fn format_architectural_result(result: &ArchitecturalResult) -> String {
+    match result {
+        ArchitecturalResult::Stability(result) => format!(
+            "Building stability verification\n\
+             Resisting moment (Me): {:.2} kN·m\n\
+             Overturning moment (Mv): {:.2} kN·m\n\
+             Stability ratio (Me/Mv): {:.3}\n\
+             Safety margin: {:.3}\n\
+             The building is {}",
+            result.resisting_moment,
+            result.overturning_moment,
+            result.stability_ratio,
+            result.safety_margin,
+            if result.is_stable { "stable" } else { "unstable" },
+        ),
+        ArchitecturalResult::MinimumDeadLoad(dead_load) => {
+            format!("Minimum dead load: {:.3} kN/m²", dead_load)
+        }
+        ArchitecturalResult::Seismic(result) => format!(
+            "Seismic base shear\n\
+             Seismic coefficient (Cs): {:.4}\n\
+             Base shear (V): {:.2} kN",
+            result.seismic_coefficient, result.base_shear,
+        ),
+        ArchitecturalResult::BeamDeflection(result) => format!(
+            "Beam deflection\n\
+             Maximum deflection: {:.2} mm\n\
+             Span/deflection ratio: L/{:.0}",
+            result.deflection * 1000.0,
+            result.span_ratio,
+        ),
+        ArchitecturalResult::WindStiffness(result) => format!(
+            "Wind stiffness compliance\n\
+             Slenderness ratio: {:.3}\n\
+             Minimum ratio: {:.3}\n\
+             The building is {}",
+            result.slenderness_ratio,
+            result.min_ratio,
+            if result.is_compliant { "compliant" } else { "not compliant" },
+        ),
+        ArchitecturalResult::WindPressure(pressure) => format!(
+            "Wind pressure: {:.2} N/m² ({:.3} kN/m²)",
+            pressure,
+            pressure / 1000.0,
+        ),
+        ArchitecturalResult::LoadCombination(result) => format!(
+            "Load combination {}\n\
+             Factored load: {:.3}",
+            result.combination, result.factored_load,
+        ),
+        ArchitecturalResult::GoldenPartition(result) => format!(
+            "Golden ratio partition\n\
+             Major segment: {:.3} m\n\
+             Minor segment: {:.3} m",
+            result.major, result.minor,
+        ),
+        ArchitecturalResult::FootprintArea(area) => format!("Footprint area: {:.2} m²", area),
+        ArchitecturalResult::Volume(volume) => format!("Building volume: {:.2} m³", volume),
+        ArchitecturalResult::HeightSlenderness(result) => format!(
+            "Height slenderness compliance\n\
+             Slenderness ratio: {:.3}\n\
+             Maximum ratio: {:.3}\n\
+             The building is {}",
+            result.slenderness_ratio,
+            result.max_ratio,
+            if result.is_compliant { "compliant" } else { "not compliant" },
+        ),
+    }
+}
+
+/// Run the same architectural calculation over many parameter rows, e.g. the
+/// rows of a CSV file, formatting each outcome with `calc_architecture_command`
+///
+/// Returns one string per row, in order. A failing row is reported in its own
+/// string and does not stop the others.
+pub fn calc_architecture_batch(calculation_type: &str, rows: Vec<Vec<f64>>) -> Vec<String> {
+    rows.iter()
+        .map(|params| calc_architecture_command(calculation_type, params))
+        .collect()
+}
+
+/// Run an architectural calculation by name and serialize the outcome as JSON
+///
+/// Results are keyed by calculation, e.g. `{"stability":{"is_stable":true,...}}`.
+/// Failures are reported as `{"error":"..."}`.
+#[cfg(feature = "serde")]
+pub fn calc_architecture_json(calculation_type: &str, params: &[f64]) -> String {
+    let value = match calc_architecture(calculation_type, params) {
+        Ok(result) => serde_json::to_value(result),
+        Err(error) => Ok(serde_json::json!({ "error": error })),
+    };
+    match value {
+        Ok(value) => value.to_string(),
+        Err(error) => serde_json::json!({ "error": error.to_string() }).to_string(),
+    }
+}
+
+// Checks that a named calculation received exactly the expected number of parameters
+fn check_param_count(calculation_type: &str, params: &[f64], expected: usize) -> Result<(), String> {
+    if params.len() != expected {
+        return Err(format!(
+            "'{}' calculation expects {} parameters, got {}",
+            calculation_type,
+            expected,
+            params.len()
+        ));
+    }
+    Ok(())
+}
+
+// Checks that a named calculation received between `min` and `max` parameters
+fn check_param_count_range(calculation_type: &str, params: &[f64], min: usize, max: usize) -> Result<(), String> {
+    if params.len() < min || params.len() > max {
+        return Err(format!(
+            "'{}' calculation expects {} to {} parameters, got {}",
+            calculation_type,
+            min,
+            max,
+            params.len()
+        ));
+    }
+    Ok(())
+}
+
+// Converts a load combination number, starting at 1, into its name
+fn load_combination_from_param(value: f64) -> Result<&'static str, String> {
+    if value.fract() != 0.0 || value < 1.0 || value > LOAD_COMBINATIONS.len() as f64 {
+        return Err(format!(
+            "Load combination number must be a whole number from 1 to {}",
+            LOAD_COMBINATIONS.len()
+        ));
+    }
+    Ok(LOAD_COMBINATIONS[value as usize - 1].0)
+}
+
+// Converts a floor count received as a number into a whole `u32`
+fn floor_count_from_param(value: f64) -> Result<u32, String> {
+    if !value.is_finite() || value < 0.0 || value.fract() != 0.0 {
+        return Err("Number of floors must be a non-negative whole number".to_string());
+    }
+    if value > u32::MAX as f64 {
+        return Err("Number of floors is out of range".to_string());
+    }
+    Ok(value as u32)
 }
 
 /// Function registry for math functions
@@ -396,22 +1654,104 @@ pub fn get_math_functions() -> std::collections::HashMap<String, fn(f64) -> f64>
     functions.insert("sin".to_string(), MathModule::sin as fn(f64) -> f64);
     functions.insert("cos".to_string(), MathModule::cos as fn(f64) -> f64);
     functions.insert("tan".to_string(), MathModule::tan as fn(f64) -> f64);
+    functions.insert("sind".to_string(), MathModule::sind as fn(f64) -> f64);
+    functions.insert("cosd".to_string(), MathModule::cosd as fn(f64) -> f64);
+    functions.insert("tand".to_string(), MathModule::tand as fn(f64) -> f64);
+    functions.insert("asin".to_string(), MathModule::asin as fn(f64) -> f64);
+    functions.insert("acos".to_string(), MathModule::acos as fn(f64) -> f64);
+    functions.insert("atan".to_string(), MathModule::atan as fn(f64) -> f64);
     functions.insert("sqrt".to_string(), MathModule::sqrt as fn(f64) -> f64);
     functions.insert("log".to_string(), MathModule::log as fn(f64) -> f64);
+    functions.insert("log10".to_string(), MathModule::log10 as fn(f64) -> f64);
+    functions.insert("log2".to_string(), MathModule::log2 as fn(f64) -> f64);
     functions.insert("exp".to_string(), MathModule::exp as fn(f64) -> f64);
     functions.insert("abs".to_string(), MathModule::abs as fn(f64) -> f64);
+    functions.insert("sign".to_string(), MathModule::sign as fn(f64) -> f64);
+    functions.insert("floor".to_string(), MathModule::floor as fn(f64) -> f64);
+    functions.insert("ceil".to_string(), MathModule::ceil as fn(f64) -> f64);
+    functions.insert("round".to_string(), MathModule::round as fn(f64) -> f64);
+    functions.insert("trunc".to_string(), MathModule::trunc as fn(f64) -> f64);
+    functions.insert("factorial".to_string(), MathModule::factorial as fn(f64) -> f64);
     functions.insert("to_radians".to_string(), MathModule::to_radians as fn(f64) -> f64);
     functions.insert("to_degrees".to_string(), MathModule::to_degrees as fn(f64) -> f64);
+    functions.insert("percent_to_bps".to_string(), MathModule::percent_to_bps as fn(f64) -> f64);
+    functions.insert("bps_to_percent".to_string(), MathModule::bps_to_percent as fn(f64) -> f64);
     
     functions
 }
 
+/// Function registry for two-argument math functions
+pub fn get_binary_math_functions() -> std::collections::HashMap<String, fn(f64, f64) -> f64> {
+    let mut functions = std::collections::HashMap::new();
+
+    functions.insert("pow".to_string(), MathModule::pow as fn(f64, f64) -> f64);
+    functions.insert("min".to_string(), MathModule::min as fn(f64, f64) -> f64);
+    functions.insert("max".to_string(), MathModule::max as fn(f64, f64) -> f64);
+    functions.insert("atan2".to_string(), MathModule::atan2 as fn(f64, f64) -> f64);
+    functions.insert("hypot".to_string(), MathModule::hypot as fn(f64, f64) -> f64);
+    functions.insert("log_base".to_string(), MathModule::log_base as fn(f64, f64) -> f64);
+    functions.insert("gcd".to_string(), MathModule::gcd as fn(f64, f64) -> f64);
+    functions.insert("lcm".to_string(), MathModule::lcm as fn(f64, f64) -> f64);
+    functions.insert("mod".to_string(), MathModule::modulo as fn(f64, f64) -> f64);
+    functions.insert("percent_of".to_string(), percent_of_or_nan as fn(f64, f64) -> f64);
+    functions.insert("apply_percent".to_string(), MathModule::apply_percent as fn(f64, f64) -> f64);
+
+    functions
+}
+
+// Registry version of `MathModule::percent_of`, NaN for a zero whole like the other functions
+fn percent_of_or_nan(part: f64, whole: f64) -> f64 {
+    MathModule::percent_of(part, whole).unwrap_or(f64::NAN)
+}
+
+/// Function registry for three-argument math functions
+pub fn get_ternary_math_functions() -> std::collections::HashMap<String, fn(f64, f64, f64) -> f64> {
+    let mut functions = std::collections::HashMap::new();
+
+    functions.insert("clamp".to_string(), MathModule::clamp as fn(f64, f64, f64) -> f64);
+    functions.insert("safe_divide".to_string(), MathModule::safe_divide as fn(f64, f64, f64) -> f64);
+
+    functions
+}
+
+/// Functions taking any number of arguments, handled by the interpreter
+/// rather than through the fixed-arity registries
+pub const AGGREGATE_FUNCTIONS: &[&str] = &["min", "max", "sum", "mean", "stddev"];
+
+/// Names of every built-in math function, sorted
+///
+/// Collected from the function registries and `AGGREGATE_FUNCTIONS`, so the
+/// list always matches what scripts can call.
+pub fn math_function_names() -> Vec<String> {
+    let mut names: Vec<String> = get_math_functions()
+        .into_keys()
+        .chain(get_binary_math_functions().into_keys())
+        .chain(get_ternary_math_functions().into_keys())
+        .chain(AGGREGATE_FUNCTIONS.iter().map(|name| name.to_string()))
+        .collect();
+    names.sort();
+    // `min` and `max` are both binary and variadic
+    names.dedup();
+    names
+}
+
+/// Names of every built-in math constant, sorted
+pub fn math_constant_names() -> Vec<String> {
+    let mut names: Vec<String> = get_math_constants().into_keys().collect();
+    names.sort();
+    names
+}
+
 /// Function registry for math constants
 pub fn get_math_constants() -> std::collections::HashMap<String, f64> {
     let mut constants = std::collections::HashMap::new();
     
     constants.insert("PI".to_string(), MathModule::pi());
     constants.insert("E".to_string(), MathModule::e());
+    constants.insert("TAU".to_string(), MathModule::tau());
+    constants.insert("SQRT_2".to_string(), MathModule::sqrt_2());
+    constants.insert("LN_2".to_string(), MathModule::ln_2());
+    constants.insert("PHI".to_string(), MathModule::phi());
     
     constants
 } 
\ No newline at end of file
@@ -1,25 +1,123 @@
 // Parser + AST Definitions
+use crate::interpreter::RuntimeError;
+use crate::tokenizer::{check_tokens, LexError, Token};
 use regex::Error as RegexError;
-use std::{fs::File, io::Read, result::Result};
+use std::{fmt, fs::File, io::Read, rc::Rc, result::Result};
 use thiserror::Error;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Value {
+    Integer(i64),
     Number(f64),
     String(String),
+    Bool(bool),
     None,
 }
 
+/// Default tolerance used by the interpreter when comparing floats with `==`, `!=`, `<`, `<=`, `>` and `>=`
+///
+/// 1e-9 absorbs the rounding error of ordinary arithmetic such as `0.1 + 0.2`
+/// while still telling apart values that differ in the ninth significant digit.
+pub const DEFAULT_EPSILON: f64 = 1e-9;
+
+impl Value {
+    /// The numeric value of an `Integer` or `Number`, as a float
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Integer(n) => Some(*n as f64),
+            Value::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Compare two values, allowing numbers to differ by up to `epsilon`
+    ///
+    /// The tolerance is absolute for numbers with magnitude up to 1 and relative
+    /// to the larger magnitude above that. Two integers, infinities, NaN and
+    /// non-number variants compare strictly.
+    pub fn approx_eq(&self, other: &Value, epsilon: f64) -> bool {
+        if let (Value::Integer(a), Value::Integer(b)) = (self, other) {
+            return a == b;
+        }
+        match (self.as_number(), other.as_number()) {
+            (Some(a), Some(b)) => {
+                if a == b {
+                    return true;
+                }
+                // Infinities and NaN only ever compare strictly
+                if !a.is_finite() || !b.is_finite() {
+                    return false;
+                }
+                (a - b).abs() <= epsilon * a.abs().max(b.abs()).max(1.0)
+            }
+            _ => self == other,
+        }
+    }
+}
+
+// Integers and floats compare by numeric value, so `Integer(2) == Number(2.0)`
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Integer(a), Value::Number(b)) | (Value::Number(b), Value::Integer(a)) => {
+                *a as f64 == *b
+            }
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::None, Value::None) => true,
+            _ => false,
+        }
+    }
+}
+
+// Ordering follows equality: numbers compare by numeric value whatever their type,
+// strings lexicographically and booleans with `false` first. Values of different
+// kinds, such as a number and a string, are unordered.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => a.partial_cmp(b),
+            (Value::Integer(a), Value::Number(b)) => (*a as f64).partial_cmp(b),
+            (Value::Number(a), Value::Integer(b)) => a.partial_cmp(&(*b as f64)),
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            (Value::None, Value::None) => Some(std::cmp::Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Integer(num) => write!(f, "{}", num),
+            Value::Number(num) => write!(f, "{}", num),
+            Value::String(text) => write!(f, "{}", text),
+            Value::Bool(flag) => write!(f, "{}", flag),
+            Value::None => write!(f, "None"),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ScriptError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     #[error("Regex error: {0}")]
     Regex(#[from] RegexError),
+    #[error("Lexical error: {0}")]
+    Lex(#[from] LexError),
+    #[error("Parse error: {0}")]
+    Parse(String),
+    #[error("Runtime error: {0}")]
+    Runtime(#[from] RuntimeError),
 }
 
 pub trait Node {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value;
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError>;
 }
 
 pub struct EvalMathExp {
@@ -35,7 +133,7 @@ impl EvalMathExp {
 }
 
 impl Node for EvalMathExp {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_eval_math_exp(self)
     }
 }
@@ -53,29 +151,73 @@ impl BinOp {
 }
 
 impl Node for BinOp {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_bin_op(self)
     }
 }
 
+/// Prefix `-` or `+` applied to an expression, e.g. `-(3 + 4)` or `-x`
+pub struct UnaryOp {
+    pub op: String,
+    pub operand: Box<dyn Node>,
+}
+
+impl UnaryOp {
+    pub fn parse(op: String, operand: Box<dyn Node>) -> Self {
+        Self { op, operand }
+    }
+}
+
+impl Node for UnaryOp {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
+        visitor.visit_unary_op(self)
+    }
+}
+
 pub struct Number {
     pub value: f64,
 }
 
 impl Number {
-    pub fn parse(value: &str) -> Self {
-        Self {
-            value: value.parse().unwrap(),
+    pub fn parse(value: &str) -> Result<Self, ScriptError> {
+        match value.parse() {
+            Ok(value) => Ok(Self { value }),
+            Err(_) => Err(ScriptError::Parse(format!(
+                "Invalid number literal '{}'",
+                value
+            ))),
         }
     }
 }
 
 impl Node for Number {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_number(self)
     }
 }
 
+pub struct Integer {
+    pub value: i64,
+}
+
+impl Integer {
+    pub fn parse(value: &str) -> Result<Self, ScriptError> {
+        match value.parse() {
+            Ok(value) => Ok(Self { value }),
+            Err(_) => Err(ScriptError::Parse(format!(
+                "Invalid integer literal '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+impl Node for Integer {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
+        visitor.visit_integer(self)
+    }
+}
+
 pub struct Var {
     pub name: String,
 }
@@ -87,7 +229,7 @@ impl Var {
 }
 
 impl Node for Var {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_var(self)
     }
 }
@@ -95,16 +237,32 @@ impl Node for Var {
 pub struct Assign {
     pub name: String,
     pub expr: Box<dyn Node>,
+    /// `var name := expr` declares a new variable, `name := expr` reassigns an existing one
+    pub is_declaration: bool,
 }
 
 impl Assign {
+    /// Reassignment of an existing variable, `name := expr`
     pub fn parse(name: String, expr: Box<dyn Node>) -> Self {
-        Self { name, expr }
+        Self {
+            name,
+            expr,
+            is_declaration: false,
+        }
+    }
+
+    /// Declaration of a new variable, `var name := expr`
+    pub fn declare(name: String, expr: Box<dyn Node>) -> Self {
+        Self {
+            name,
+            expr,
+            is_declaration: true,
+        }
     }
 }
 
 impl Node for Assign {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_assign(self)
     }
 }
@@ -120,7 +278,7 @@ impl StringLiteral {
 }
 
 impl Node for StringLiteral {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_string_literal(self)
     }
 }
@@ -137,11 +295,75 @@ impl FunctionCall {
 }
 
 impl Node for FunctionCall {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_function_call(self)
     }
 }
 
+pub struct FunctionDef {
+    pub name: String,
+    pub params: Vec<String>,
+    // Shared so the interpreter can keep the body after the AST is dropped
+    pub body: Rc<dyn Node>,
+}
+
+impl FunctionDef {
+    pub fn parse(name: String, params: Vec<String>, body: Box<dyn Node>) -> Self {
+        Self {
+            name,
+            params,
+            body: Rc::from(body),
+        }
+    }
+}
+
+impl Node for FunctionDef {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
+        visitor.visit_function_def(self)
+    }
+}
+
+/// Conditional expression `if cond then a else b`, also written `cond ? a : b`
+pub struct If {
+    pub cond: Box<dyn Node>,
+    pub then_branch: Box<dyn Node>,
+    pub else_branch: Box<dyn Node>,
+}
+
+impl If {
+    pub fn parse(cond: Box<dyn Node>, then_branch: Box<dyn Node>, else_branch: Box<dyn Node>) -> Self {
+        Self {
+            cond,
+            then_branch,
+            else_branch,
+        }
+    }
+}
+
+impl Node for If {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
+        visitor.visit_if(self)
+    }
+}
+
+/// Loop `while cond do body`, evaluating `body` as long as `cond` is true
+pub struct While {
+    pub cond: Box<dyn Node>,
+    pub body: Box<dyn Node>,
+}
+
+impl While {
+    pub fn parse(cond: Box<dyn Node>, body: Box<dyn Node>) -> Self {
+        Self { cond, body }
+    }
+}
+
+impl Node for While {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
+        visitor.visit_while(self)
+    }
+}
+
 pub struct Comment {
     pub value: String,
 }
@@ -153,34 +375,447 @@ impl Comment {
 }
 
 impl Node for Comment {
-    fn accept(&self, visitor: &mut dyn Visitor) -> Value {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
         visitor.visit_comment(self)
     }
 }
 
+/// A statement ended by `;`, run for its effects only: its value is discarded
+pub struct Discard {
+    pub statement: Box<dyn Node>,
+}
+
+impl Discard {
+    pub fn parse(statement: Box<dyn Node>) -> Self {
+        Self { statement }
+    }
+}
+
+impl Node for Discard {
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<Value, RuntimeError> {
+        visitor.visit_discard(self)
+    }
+}
+
 pub trait Visitor {
-    fn visit_eval_math_exp(&mut self, node: &EvalMathExp) -> Value;
-    fn visit_bin_op(&mut self, node: &BinOp) -> Value;
-    fn visit_number(&mut self, node: &Number) -> Value;
-    fn visit_var(&mut self, node: &Var) -> Value;
-    fn visit_assign(&mut self, node: &Assign) -> Value;
-    fn visit_string_literal(&mut self, node: &StringLiteral) -> Value;
-    fn visit_function_call(&mut self, node: &FunctionCall) -> Value;
-    fn visit_comment(&mut self, node: &Comment) -> Value;
+    fn visit_eval_math_exp(&mut self, node: &EvalMathExp) -> Result<Value, RuntimeError>;
+    fn visit_bin_op(&mut self, node: &BinOp) -> Result<Value, RuntimeError>;
+    fn visit_unary_op(&mut self, node: &UnaryOp) -> Result<Value, RuntimeError>;
+    fn visit_number(&mut self, node: &Number) -> Result<Value, RuntimeError>;
+    fn visit_integer(&mut self, node: &Integer) -> Result<Value, RuntimeError>;
+    fn visit_var(&mut self, node: &Var) -> Result<Value, RuntimeError>;
+    fn visit_assign(&mut self, node: &Assign) -> Result<Value, RuntimeError>;
+    fn visit_string_literal(&mut self, node: &StringLiteral) -> Result<Value, RuntimeError>;
+    fn visit_function_call(&mut self, node: &FunctionCall) -> Result<Value, RuntimeError>;
+    fn visit_function_def(&mut self, node: &FunctionDef) -> Result<Value, RuntimeError>;
+    fn visit_if(&mut self, node: &If) -> Result<Value, RuntimeError>;
+    fn visit_while(&mut self, node: &While) -> Result<Value, RuntimeError>;
+    fn visit_comment(&mut self, node: &Comment) -> Result<Value, RuntimeError>;
+    fn visit_discard(&mut self, node: &Discard) -> Result<Value, RuntimeError>;
+}
+
+/// Parse a complete expression from a token stream
+///
+/// Binary operators are resolved by precedence climbing, from lowest to highest:
+///
+/// | Operators                     | Precedence | Associativity |
+/// |-------------------------------|------------|---------------|
+/// | `==` `!=` `<` `<=` `>` `>=`   | 1          | left          |
+/// | `+` `-`                       | 2          | left          |
+/// | `*` `/` `%`                   | 3          | left          |
+/// | `^`                           | 4          | right         |
+///
+/// The conditional operator `cond ? a : b` binds looser than all of them and
+/// is right-associative, so `x < 0 ? -1 : x == 0 ? 0 : 1` needs no parentheses.
+/// It is the same as `if cond then a else b`.
+/// Parenthesized groups override precedence, e.g. `(2 + 3) * 4`.
+/// Prefix `-` and `+` bind tighter than `*` but looser than `^`, so `-x ^ 2`
+/// is `-(x ^ 2)`, and likewise `-2 ^ 2` is `-(2 ^ 2)`. Otherwise a minus
+/// directly before a number is part of the literal.
+/// Primary expressions are integer and float numbers, string literals, variables,
+/// function calls with comma separated arguments, declarations (`var x := expr`)
+/// and reassignments (`x := expr`), whose value is the assigned value,
+/// function definitions (`fn name(a, b) := expr`), conditionals
+/// (`if cond then a else b`) and loops (`while cond do body`). The `else`
+/// branch is required and, like the body of a function or a loop, extends as
+/// far as possible: `if c then 1 else 2 + 3` adds 3 to the `else` branch only.
+///
+/// # Returns
+/// * `Ok(Box<dyn Node>)` - The root node of the expression tree
+/// * `Err(ScriptError::Lex)` if the tokenizer reported an error
+/// * `Err(ScriptError::Parse)` if the tokens are not a single valid expression
+pub fn parse_expr(tokens: &[Token]) -> Result<Box<dyn Node>, ScriptError> {
+    check_tokens(tokens)?;
+    let mut parser = Parser::new(tokens);
+    let expr = parser.parse_expression(0)?;
+    if let Some(token) = parser.peek() {
+        return Err(ScriptError::Parse(format!(
+            "Unexpected token after expression: {:?}",
+            token
+        )));
+    }
+    Ok(expr)
+}
+
+// Left and right binding powers of the infix operators. A right power higher
+// than the left one makes the operator left-associative, a lower one makes it
+// right-associative, as `^` is: `2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`.
+fn infix_binding_power(op: &str) -> Option<(u8, u8)> {
+    match op {
+        "==" | "!=" | "<" | "<=" | ">" | ">=" => Some((1, 2)),
+        "+" | "-" => Some((3, 4)),
+        "*" | "/" | "%" => Some((5, 6)),
+        "^" => Some((8, 7)),
+        _ => None,
+    }
+}
+
+// Binding power of the operand of a prefix `-` or `+`, between those of `*` and `^`
+const PREFIX_BINDING_POWER: u8 = 7;
+
+// Cursor over a token stream used by the expression parser
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    // Parentheses open around the current position, inside which line breaks
+    // do not end the expression
+    paren_depth: usize,
+}
+
+// Tokens that `peek` and `next` look past
+fn is_layout(token: &Token) -> bool {
+    matches!(token, Token::Comment(_) | Token::Newline)
 }
 
-pub fn parse_script(source: String) -> Result<(), ScriptError> {
-    use crate::tokenizer::tokenize;
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            paren_depth: 0,
+        }
+    }
+
+    // Comments and line breaks never take part in expressions, `peek` and `next`
+    // look past them. Where a line break ends an expression is decided by `at_line_end`.
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens[self.pos..].iter().find(|token| !is_layout(token))
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        while self.tokens.get(self.pos).is_some_and(is_layout) {
+            self.pos += 1;
+        }
+        let token = self.tokens.get(self.pos);
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    // Whether a line break comes before the next token, outside parentheses, so
+    // that the expression parsed so far must not be continued by that token.
+    // A line ending with an operator is continued, since the operand is required.
+    fn at_line_end(&self) -> bool {
+        self.paren_depth == 0
+            && self.tokens[self.pos..]
+                .iter()
+                .take_while(|token| is_layout(token))
+                .any(|token| *token == Token::Newline)
+    }
 
+    // Consumes the line breaks found where a statement starts
+    fn skip_newlines(&mut self) -> bool {
+        let start = self.pos;
+        while let Some(Token::Newline) = self.tokens.get(self.pos) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    // Consumes a comment found where a statement starts
+    fn next_comment(&mut self) -> Option<&'a str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Comment(text)) => {
+                self.pos += 1;
+                Some(text)
+            }
+            _ => None,
+        }
+    }
+
+    // Checks that the statement just parsed is followed by a separator: a line
+    // break, a `;`, a comment or the end of input, none of which is consumed
+    fn expect_statement_end(&self) -> Result<(), ScriptError> {
+        match self.tokens.get(self.pos) {
+            None | Some(Token::Newline) | Some(Token::Semicolon) | Some(Token::Comment(_)) => Ok(()),
+            Some(token) => Err(ScriptError::Parse(format!(
+                "Expected a line break or ';' after a statement, found {:?}",
+                token
+            ))),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ScriptError> {
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            Some(token) => Err(ScriptError::Parse(format!(
+                "Expected {:?}, found {:?}",
+                expected, token
+            ))),
+            None => Err(ScriptError::Parse(format!(
+                "Expected {:?}, found end of input",
+                expected
+            ))),
+        }
+    }
+
+    fn parse_expression(&mut self, min_bp: u8) -> Result<Box<dyn Node>, ScriptError> {
+        let mut left = self.parse_primary()?;
+
+        loop {
+            if self.at_line_end() {
+                break;
+            }
+            match self.peek() {
+                Some(Token::Operator(op)) => {
+                    let Some((left_bp, right_bp)) = infix_binding_power(op) else {
+                        break;
+                    };
+                    if left_bp < min_bp {
+                        break;
+                    }
+                    self.next();
+                    let right = self.parse_expression(right_bp)?;
+                    left = Box::new(BinOp::parse(left, op.clone(), right));
+                }
+                // `cond ? a : b` binds looser than any infix operator, so it is
+                // only taken where a whole expression may end
+                Some(Token::Question) if min_bp == 0 => {
+                    self.next();
+                    let then_branch = self.parse_expression(0)?;
+                    self.expect(Token::Colon)?;
+                    let else_branch = self.parse_expression(0)?;
+                    left = Box::new(If::parse(left, then_branch, else_branch));
+                }
+                _ => break,
+            }
+        }
+
+        Ok(left)
+    }
+
+    fn parse_primary(&mut self) -> Result<Box<dyn Node>, ScriptError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Box::new(Number { value: *value })),
+            Some(Token::Integer(value)) => Ok(Box::new(Integer { value: *value })),
+            Some(Token::StringLiteral(value)) => Ok(Box::new(StringLiteral::parse(value.clone()))),
+            Some(Token::Fn) => self.parse_function_def(),
+            Some(Token::If) => {
+                let cond = self.parse_expression(0)?;
+                self.expect(Token::Then)?;
+                let then_branch = self.parse_expression(0)?;
+                self.expect(Token::Else)?;
+                let else_branch = self.parse_expression(0)?;
+                Ok(Box::new(If::parse(cond, then_branch, else_branch)))
+            }
+            Some(Token::While) => {
+                let cond = self.parse_expression(0)?;
+                self.expect(Token::Do)?;
+                let body = self.parse_expression(0)?;
+                Ok(Box::new(While::parse(cond, body)))
+            }
+            // `f` followed by `(` on the next line is not a call
+            Some(Token::Identifier(name)) if self.at_line_end() => Ok(Box::new(Var::parse(name.clone()))),
+            Some(Token::Identifier(name)) => match self.peek() {
+                Some(Token::LParen) => {
+                    self.next();
+                    self.paren_depth += 1;
+                    let args = self.parse_arguments()?;
+                    self.paren_depth -= 1;
+                    Ok(Box::new(FunctionCall::parse(name.clone(), args)))
+                }
+                Some(Token::Assign) => {
+                    self.next();
+                    let expr = self.parse_expression(0)?;
+                    Ok(Box::new(Assign::parse(name.clone(), expr)))
+                }
+                _ => Ok(Box::new(Var::parse(name.clone()))),
+            },
+            Some(Token::Var) => {
+                let name = match self.next() {
+                    Some(Token::Identifier(name)) => name.clone(),
+                    _ => {
+                        return Err(ScriptError::Parse(
+                            "Expected a variable name after 'var'".to_string(),
+                        ))
+                    }
+                };
+                self.expect(Token::Assign)?;
+                let expr = self.parse_expression(0)?;
+                Ok(Box::new(Assign::declare(name, expr)))
+            }
+            Some(Token::LParen) => {
+                self.paren_depth += 1;
+                let expr = self.parse_expression(0)?;
+                self.expect(Token::RParen)?;
+                self.paren_depth -= 1;
+                Ok(expr)
+            }
+            Some(Token::Operator(op)) if op == "-" || op == "+" => {
+                let operand = self.parse_expression(PREFIX_BINDING_POWER)?;
+                Ok(Box::new(UnaryOp::parse(op.clone(), operand)))
+            }
+            Some(token) => Err(ScriptError::Parse(format!(
+                "Unexpected token: {:?}",
+                token
+            ))),
+            None => Err(ScriptError::Parse("Unexpected end of input".to_string())),
+        }
+    }
+
+    // Parses `name(a, b) := body`, the `fn` keyword having been consumed
+    fn parse_function_def(&mut self) -> Result<Box<dyn Node>, ScriptError> {
+        let name = match self.next() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(ScriptError::Parse("Expected a function name after 'fn'".to_string())),
+        };
+        self.expect(Token::LParen)?;
+        let mut params = Vec::new();
+        if let Some(Token::RParen) = self.peek() {
+            self.next();
+        } else {
+            loop {
+                match self.next() {
+                    Some(Token::Identifier(param)) => params.push(param.clone()),
+                    Some(token) => {
+                        return Err(ScriptError::Parse(format!(
+                            "Expected a parameter name in '{}', found {:?}",
+                            name, token
+                        )))
+                    }
+                    None => return Err(ScriptError::Parse("Unclosed parameter list".to_string())),
+                }
+                match self.next() {
+                    Some(Token::Comma) => continue,
+                    Some(Token::RParen) => break,
+                    Some(token) => {
+                        return Err(ScriptError::Parse(format!(
+                            "Expected ',' or ')' in parameter list, found {:?}",
+                            token
+                        )))
+                    }
+                    None => return Err(ScriptError::Parse("Unclosed parameter list".to_string())),
+                }
+            }
+        }
+        self.expect(Token::Assign)?;
+        let body = self.parse_expression(0)?;
+        Ok(Box::new(FunctionDef::parse(name, params, body)))
+    }
+
+    // Parses the arguments of a call, the opening paren having been consumed
+    fn parse_arguments(&mut self) -> Result<Vec<Box<dyn Node>>, ScriptError> {
+        let mut args = Vec::new();
+        if let Some(Token::RParen) = self.peek() {
+            self.next();
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_expression(0)?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => return Ok(args),
+                Some(token) => {
+                    return Err(ScriptError::Parse(format!(
+                        "Expected ',' or ')' in argument list, found {:?}",
+                        token
+                    )))
+                }
+                None => return Err(ScriptError::Parse("Unclosed argument list".to_string())),
+            }
+        }
+    }
+}
+
+/// Parse a sequence of statements from a token stream
+///
+/// Statements are expressions (including assignments) written one after
+/// the other. Each one ends at a line break, unless it is inside parentheses
+/// or after an operator still waiting for its right operand, or at a `;`,
+/// which allows several statements on one line as in `x := 1; x + 1`.
+/// Anything else after a statement on the same line, as in `x := 1 2`, is
+/// an error. A statement ended by `;` becomes a `Discard` node, which has no
+/// value, so `x := 1;` leaves a script or a REPL line without one.
+/// Comments found between statements, on their own line or after a statement,
+/// become `Comment` nodes; comments inside an expression are skipped.
+pub fn parse_statements(tokens: &[Token]) -> Result<Vec<Box<dyn Node>>, ScriptError> {
+    check_tokens(tokens)?;
+    let mut parser = Parser::new(tokens);
+    let mut statements = Vec::new();
+    loop {
+        if parser.skip_newlines() {
+            continue;
+        }
+        if let Some(text) = parser.next_comment() {
+            statements.push(Box::new(Comment::parse(text.to_string())) as Box<dyn Node>);
+            continue;
+        }
+        match parser.peek() {
+            None => break,
+            // Repeated separators add no statement
+            Some(Token::Semicolon) => {
+                parser.next();
+                continue;
+            }
+            Some(_) => {
+                let statement = parser.parse_expression(0)?;
+                parser.expect_statement_end()?;
+                if let Some(Token::Semicolon) = parser.tokens.get(parser.pos) {
+                    parser.pos += 1;
+                    statements.push(Box::new(Discard::parse(statement)));
+                } else {
+                    statements.push(statement);
+                }
+            }
+        }
+    }
+    Ok(statements)
+}
+
+/// Read the script at path `source` and parse it into a list of statements
+///
+/// Input the tokenizer does not recognize is reported as `ScriptError::Lex`
+/// with its text and character position.
+pub fn parse_script(source: String) -> Result<Vec<Box<dyn Node>>, ScriptError> {
     let mut file = File::open(source)?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
 
-    let tokens = tokenize(&content);
+    parse_str(&content)
+}
 
-    for token in tokens {
-        println!("Parsed token: {:?}", token);
-    }
+/// Parse the source code of a script, already in memory, into a list of statements
+///
+/// Input the tokenizer does not recognize is reported as `ScriptError::Lex`
+/// with its text and character position.
+pub fn parse_str(content: &str) -> Result<Vec<Box<dyn Node>>, ScriptError> {
+    parse_statements(&source_tokens(content)?)
+}
+
+/// Parse a single expression, given as source code, into its expression tree
+///
+/// Input the tokenizer does not recognize is reported as `ScriptError::Lex`
+/// with its text and character position, as by `parse_str`.
+pub fn parse_expr_str(content: &str) -> Result<Box<dyn Node>, ScriptError> {
+    parse_expr(&source_tokens(content)?)
+}
+
+// Tokenizes source code, failing on the first token the tokenizer did not recognize
+fn source_tokens(content: &str) -> Result<Vec<Token>, ScriptError> {
+    use crate::tokenizer::{check_unknown, tokenize_with_positions};
 
-    Ok(())
+    let positioned = tokenize_with_positions(content);
+    check_unknown(&positioned)?;
+    Ok(positioned.into_iter().map(|(token, _)| token).collect())
 }
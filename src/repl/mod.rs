@@ -1,19 +1,425 @@
 // REPL (Read-Eval-Print Loop)
 
-use std::io::{self};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::interpreter::{AngleMode, Interpreter};
+use crate::math::{math_constant_names, math_function_names, MathModule};
+use crate::parser::{parse_script, ScriptError, Value};
+use crate::runtime::run_source_with_interpreter;
+use crate::tokenizer::{tokenize, LexError, Token};
+
+/// Interactive session that keeps a single interpreter alive between lines,
+/// so variables assigned on one line are available on the next ones
+pub struct Repl {
+    interpreter: Interpreter,
+    history: Vec<String>,
+    session_start: usize,
+    history_path: Option<PathBuf>,
+    // Decimals numeric results are rounded to when printed, if set
+    precision: Option<u32>,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            history: Vec::new(),
+            session_start: 0,
+            history_path: None,
+            precision: None,
+        }
+    }
+
+    /// Print numeric results rounded to `decimals` decimals, or in full with `None`
+    pub fn with_precision(mut self, decimals: Option<u32>) -> Self {
+        self.precision = decimals;
+        self
+    }
+
+    /// Persist entered lines to `path`, loading the lines saved by previous sessions first.
+    /// A missing file starts an empty history; other read or write failures are reported
+    /// on stderr and the session continues without persistence.
+    pub fn with_history_file(mut self, path: PathBuf) -> Self {
+        match fs::read_to_string(&path) {
+            Ok(content) => self.history.extend(content.lines().map(str::to_string)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => {
+                eprintln!("Could not read history file {}: {}", path.display(), error);
+                return self;
+            }
+        }
+        self.session_start = self.history.len();
+        self.history_path = Some(path);
+        self
+    }
+
+    /// Every known line, oldest first: the ones loaded from the history file
+    /// followed by the ones entered in this session
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Parse and evaluate one line of input as a script of its own
+    ///
+    /// A line follows the statement grammar of scripts: it may hold several
+    /// statements separated by `;` and end with a comment. Its value is the
+    /// one of the last statement that produced a value, as for a script, so a
+    /// line ending with `;` has none.
+    pub fn eval_line(&mut self, line: &str) -> Result<Value, ScriptError> {
+        run_source_with_interpreter(line, &mut self.interpreter)
+    }
+
+    /// Run every statement of the script at `path` in this session, so the
+    /// variables and functions it defines are available on the next lines
+    ///
+    /// The first declaration of a variable the session already had before the
+    /// load assigns it instead, so a file can be loaded again, or loaded after
+    /// being written by `save_variables` in the same session. Otherwise the
+    /// file runs as it would on its own: declaring one of its own variables
+    /// twice fails. Statements run before a failing one keep their effect.
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - The number of statements run
+    /// * `Err(ScriptError)` if the file cannot be read or parsed, or a statement fails
+    pub fn load_file(&mut self, path: &str) -> Result<usize, ScriptError> {
+        let statements = parse_script(path.to_string())?;
+        let session_variables = self.interpreter.variables().keys().cloned().collect();
+        let previous = self.interpreter.set_redeclarable(session_variables);
+        let result = statements
+            .iter()
+            .try_for_each(|statement| statement.accept(&mut self.interpreter).map(|_| ()));
+        self.interpreter.set_redeclarable(previous);
+        result?;
+        Ok(statements.len())
+    }
+
+    /// Write the session variables to `path` as `var name := value` lines sorted
+    /// by name, so that loading the file later restores them
+    ///
+    /// Numbers are written with enough digits to be read back exactly. NaN and
+    /// infinities have no literal form and are left out.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<String>)` - The names of the variables left out
+    /// * `Err(io::Error)` if the file cannot be written
+    pub fn save_variables(&self, path: &Path) -> io::Result<Vec<String>> {
+        let mut variables: Vec<(&String, &Value)> = self.interpreter.variables().iter().collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut content = String::new();
+        let mut skipped = Vec::new();
+        for (name, value) in variables {
+            match literal(value) {
+                Some(literal) => content.push_str(&format!("var {} := {}\n", name, literal)),
+                None => skipped.push(name.clone()),
+            }
+        }
+        fs::write(path, content)?;
+        Ok(skipped)
+    }
+
+    /// Read lines from `input` until `exit` or end of input, writing every
+    /// result or error to `output`. Errors never stop the loop.
+    ///
+    /// Input that is not complete yet (see `is_complete`) continues on the
+    /// next lines, after a `... ` prompt.
+    ///
+    /// Returns the exit code requested with `exit <code>`, or 0.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> io::Result<i32> {
+        let mut lines = input.lines();
+        let mut pending = String::new();
+
+        loop {
+            write!(output, "{}", if pending.is_empty() { "> " } else { "... " })?;
+            output.flush()?;
+
+            let Some(line) = lines.next() else {
+                // Report whatever was left incomplete
+                if !pending.is_empty() {
+                    self.eval_and_print(&pending, output)?;
+                }
+                return Ok(0);
+            };
+            let line = line?;
+            let line = line.trim();
+
+            if !pending.is_empty() {
+                pending.push(' ');
+                pending.push_str(line);
+                if is_complete(&pending) {
+                    let entry = std::mem::take(&mut pending);
+                    self.record_history(&entry);
+                    self.eval_and_print(&entry, output)?;
+                }
+                continue;
+            }
+
+            match parse_exit_command(line) {
+                Some(Ok(code)) => return Ok(code),
+                Some(Err(error)) => {
+                    writeln!(output, "Error: {}", error)?;
+                    continue;
+                }
+                None => {}
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            // Meta-commands start with `:` and are never evaluated as Oak code
+            if line.starts_with(':') {
+                self.record_history(line);
+                self.run_command(line, output)?;
+                continue;
+            }
+
+            // Incomplete input is recorded once it is complete
+            if !is_complete(line) {
+                pending.push_str(line);
+                continue;
+            }
+
+            self.record_history(line);
+            self.eval_and_print(line, output)?;
+        }
+    }
+
+    fn eval_and_print<W: Write>(&mut self, input: &str, output: &mut W) -> io::Result<()> {
+        match self.eval_line(input) {
+            Ok(Value::None) => {}
+            Ok(Value::Number(x)) => writeln!(output, "{}", Value::Number(self.rounded(x)))?,
+            Ok(value) => writeln!(output, "{}", value)?,
+            Err(error) => writeln!(output, "Error: {}", error)?,
+        }
+        Ok(())
+    }
+
+    fn record_history(&mut self, line: &str) {
+        self.history.push(line.to_string());
+
+        if let Some(path) = &self.history_path {
+            let appended = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", line));
+            if let Err(error) = appended {
+                eprintln!("Could not write history file {}: {}", path.display(), error);
+                // Stop retrying (and warning) on every line
+                self.history_path = None;
+            }
+        }
+    }
+
+    // Rounds a result for display to the configured precision
+    fn rounded(&self, x: f64) -> f64 {
+        match self.precision {
+            Some(decimals) => MathModule::round_to(x, decimals as i32).unwrap_or(x),
+            None => x,
+        }
+    }
+
+    fn run_command<W: Write>(&mut self, command: &str, output: &mut W) -> io::Result<()> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        match words.as_slice() {
+            [":vars"] => {
+                let mut variables: Vec<(&String, &Value)> = self.interpreter.variables().iter().collect();
+                variables.sort_by(|a, b| a.0.cmp(b.0));
+                for (name, value) in variables {
+                    writeln!(output, "{} = {}", name, value)?;
+                }
+            }
+            [":clear"] => {
+                self.interpreter.clear_variables();
+                writeln!(output, "Variables cleared")?;
+            }
+            [":history"] => {
+                for (index, line) in self.history[self.session_start..].iter().enumerate() {
+                    writeln!(output, "{:>4}  {}", index + 1, line)?;
+                }
+            }
+            [":funcs"] => {
+                writeln!(output, "Functions: {}", math_function_names().join(", "))?;
+                writeln!(output, "Constants: {}", math_constant_names().join(", "))?;
+            }
+            [":precision"] => match self.precision {
+                Some(decimals) => writeln!(output, "Precision: {} decimals", decimals)?,
+                None => writeln!(output, "Precision: full")?,
+            },
+            [":precision", "off"] => {
+                self.precision = None;
+                writeln!(output, "Precision: full")?;
+            }
+            [":precision", decimals] => match decimals.parse::<u32>() {
+                Ok(decimals) => {
+                    self.precision = Some(decimals);
+                    writeln!(output, "Precision: {} decimals", decimals)?;
+                }
+                Err(_) => writeln!(
+                    output,
+                    "Invalid precision '{}', expected a non-negative number of decimals or off",
+                    decimals
+                )?,
+            },
+            [":load"] => writeln!(output, "Missing file name, usage: :load <file>")?,
+            [":load", ..] => {
+                // The path is the rest of the line, spaces included
+                let path = command[":load".len()..].trim();
+                match self.load_file(path) {
+                    Ok(count) => writeln!(output, "Loaded {} statement(s) from {}", count, path)?,
+                    Err(error) => writeln!(output, "Error: {}", error)?,
+                }
+            }
+            [":save"] => writeln!(output, "Missing file name, usage: :save <file>")?,
+            [":save", ..] => {
+                let path = command[":save".len()..].trim();
+                match self.save_variables(Path::new(path)) {
+                    Ok(skipped) => {
+                        let saved = self.interpreter.variables().len() - skipped.len();
+                        writeln!(output, "Saved {} variable(s) to {}", saved, path)?;
+                        for name in skipped {
+                            writeln!(output, "Skipped '{}', its value has no literal form", name)?;
+                        }
+                    }
+                    Err(error) => writeln!(output, "Error: Could not write {}: {}", path, error)?,
+                }
+            }
+            [":mode"] => writeln!(output, "Angle mode: {}", self.interpreter.angle_mode())?,
+            [":mode", mode] => {
+                let mode: AngleMode = match mode.parse() {
+                    Ok(mode) => mode,
+                    Err(error) => {
+                        writeln!(output, "{}", error)?;
+                        return Ok(());
+                    }
+                };
+                self.interpreter.set_angle_mode(mode);
+                writeln!(output, "Angle mode: {}", mode)?;
+            }
+            [":help"] => {
+                writeln!(output, "Available commands:")?;
+                writeln!(output, "  :vars            List the assigned variables")?;
+                writeln!(output, "  :clear           Remove every assigned variable")?;
+                writeln!(output, "  :history         Show the lines entered in this session")?;
+                writeln!(output, "  :funcs           List the built-in functions and constants")?;
+                writeln!(output, "  :load <file>     Run a script in this session, keeping its variables")?;
+                writeln!(output, "  :save <file>     Write the variables to a script that :load restores")?;
+                writeln!(output, "  :precision [n]   Show or set the decimals of printed results (off for full)")?;
+                writeln!(output, "  :mode [deg|rad]  Show or set the angle unit of trigonometric functions")?;
+                writeln!(output, "  :help            Show this help")?;
+                writeln!(output, "  exit [code]      Leave the REPL with an optional exit code (also quit)")?;
+            }
+            _ => writeln!(output, "Unknown command '{}'", command)?,
+        }
+        Ok(())
+    }
+}
+
+// Source text that evaluates back to exactly `value`, if there is one
+fn literal(value: &Value) -> Option<String> {
+    match value {
+        Value::Integer(value) => Some(value.to_string()),
+        // Debug formatting is the shortest text that parses back to the same
+        // float, and keeps the decimal point of whole numbers
+        Value::Number(value) if value.is_finite() => Some(format!("{:?}", value)),
+        _ => None,
+    }
+}
+
+/// Whether `input` can be evaluated as is, or needs more lines
+///
+/// Input is incomplete while it has unclosed parentheses or an unterminated
+/// string, or when it ends with an operator, `:=`, a comma, the `?` or `:`
+/// of a conditional or one of the keywords `if`, `then`, `else`, `while` and `do`.
+pub fn is_complete(input: &str) -> bool {
+    let tokens = tokenize(input);
+    let mut depth: i32 = 0;
+    for token in &tokens {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Error(LexError::UnterminatedString(_)) => return false,
+            _ => {}
+        }
+    }
+    let last = tokens
+        .iter()
+        .rev()
+        .find(|token| !matches!(token, Token::Comment(_) | Token::Newline));
+    depth <= 0
+        && !matches!(
+            last,
+            Some(
+                Token::Operator(_)
+                    | Token::Assign
+                    | Token::Comma
+                    | Token::Question
+                    | Token::Colon
+                    | Token::If
+                    | Token::Then
+                    | Token::Else
+                    | Token::While
+                    | Token::Do
+            )
+        )
+}
+
+/// Recognize `exit` and `quit`, optionally followed by an integer exit code
+///
+/// # Returns
+/// * `None` if the line is not an exit command
+/// * `Some(Ok(code))` with the requested exit code, 0 when none is given
+/// * `Some(Err(message))` if the exit code is not a valid integer
+pub fn parse_exit_command(line: &str) -> Option<Result<i32, String>> {
+    let mut words = line.split_whitespace();
+    if !matches!(words.next(), Some("exit") | Some("quit")) {
+        return None;
+    }
+    match (words.next(), words.next()) {
+        (None, _) => Some(Ok(0)),
+        (Some(code), None) => Some(
+            code.parse()
+                .map_err(|_| format!("Invalid exit code '{}'", code)),
+        ),
+        // Anything longer, like `exit := 3`, is Oak code
+        _ => None,
+    }
+}
+
+/// Location of the REPL history file: `$OAK_HISTORY` when set, otherwise
+/// `.oak_history` in the user's home directory
+pub fn history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("OAK_HISTORY") {
+        return Some(PathBuf::from(path));
+    }
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".oak_history"))
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 pub fn start_repl() {
     let stdin = io::stdin();
-    let input = &mut String::new();
+    let stdout = io::stdout();
 
-    loop {
-        input.clear();
-        let _ = stdin.read_line(input);
+    let mut repl = Repl::new();
+    if let Some(path) = history_path() {
+        repl = repl.with_history_file(path);
+    }
 
-        if input.trim() == "exit" {
-            std::process::exit(0);
-        } else {
-            println!("{}", input);
+    match repl.run(stdin.lock(), &mut stdout.lock()) {
+        Ok(code) => std::process::exit(code),
+        Err(error) => {
+            eprintln!("REPL I/O error: {}", error);
+            std::process::exit(1);
         }
     }
 }
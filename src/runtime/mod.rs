@@ -1,10 +1,70 @@
 // Script Runner
-use crate::parser::{ScriptError, parse_script};
+use std::io::{self, Write};
+
+use crate::interpreter::Interpreter;
+use crate::parser::{Node, ScriptError, Value, parse_script, parse_str};
 
 pub fn run(source: String) -> Result<(), ScriptError> {
-    println!("Running script with Oak version 0.1.0...");
+    run_with_result(source).map(|_| ())
+}
+
+/// Run the script at path `source`, returning the value of its last statement
+/// that produced one, so trailing comments and definitions are skipped
+/// (`Value::None` if no statement produced a value)
+pub fn run_with_result(source: String) -> Result<Value, ScriptError> {
+    let mut interpreter = Interpreter::new();
+    run_with_interpreter(source, &mut interpreter)
+}
+
+/// Run the script at path `source` against an existing interpreter, so its
+/// variables can be seeded before and inspected after the run
+///
+/// Returns the value of the last statement.
+pub fn run_with_interpreter(source: String, interpreter: &mut Interpreter) -> Result<Value, ScriptError> {
+    run_with_output(source, interpreter, &mut io::sink())
+}
+
+/// Run the script at path `source` against an existing interpreter, writing
+/// the value of every statement that produces one to `output` as it runs
+///
+/// Returns the value of the last statement.
+pub fn run_with_output<W: Write>(
+    source: String,
+    interpreter: &mut Interpreter,
+    output: &mut W,
+) -> Result<Value, ScriptError> {
+    let statements = parse_script(source)?;
+    execute(&statements, interpreter, output)
+}
+
+/// Run a script given as source code rather than as a file path,
+/// returning the value of its last statement
+pub fn run_source(source: &str) -> Result<Value, ScriptError> {
+    let mut interpreter = Interpreter::new();
+    run_source_with_interpreter(source, &mut interpreter)
+}
+
+/// Run a script given as source code against an existing interpreter
+pub fn run_source_with_interpreter(source: &str, interpreter: &mut Interpreter) -> Result<Value, ScriptError> {
+    let statements = parse_str(source)?;
+    execute(&statements, interpreter, &mut io::sink())
+}
 
-    let parsed_script: Result<(), ScriptError> = parse_script(source);
+// Evaluate each statement in order, writing the ones that produce a value to
+// `output`, and return the last of those values
+fn execute<W: Write>(
+    statements: &[Box<dyn Node>],
+    interpreter: &mut Interpreter,
+    output: &mut W,
+) -> Result<Value, ScriptError> {
+    let mut last = Value::None;
+    for statement in statements {
+        let value = statement.accept(interpreter)?;
+        if value != Value::None {
+            writeln!(output, "{}", value)?;
+            last = value;
+        }
+    }
 
-    return parsed_script;
+    Ok(last)
 }
@@ -8,18 +8,18 @@ fn test_binary_operation() {
     };
 
     let expr = BinOp::parse(
-        Box::new(Number::parse("3")),
+        Box::new(Number::parse("3").unwrap()),
         "+".to_string(),
-        Box::new(Number::parse("4")),
+        Box::new(Number::parse("4").unwrap()),
     );
 
-    let assignment = Assign::parse("x".to_string(), Box::new(expr));
+    let assignment = Assign::declare("x".to_string(), Box::new(expr));
     let mut interpreter = Interpreter::new();
 
-    assignment.accept(&mut interpreter);
+    assignment.accept(&mut interpreter).unwrap();
 
     let var = Var::parse("x".to_string());
-    let result = var.accept(&mut interpreter);
+    let result = var.accept(&mut interpreter).unwrap();
 
     assert_eq!(result, Value::Number(7.0));
 }
@@ -36,33 +36,33 @@ fn test_math_functions() {
     // Test sin function
     let sin_call = FunctionCall::parse(
         "sin".to_string(),
-        vec![Box::new(Number::parse("0"))],
+        vec![Box::new(Number::parse("0").unwrap())],
     );
-    let result = sin_call.accept(&mut interpreter);
+    let result = sin_call.accept(&mut interpreter).unwrap();
     assert_eq!(result, Value::Number(0.0));
 
     // Test cos function
     let cos_call = FunctionCall::parse(
         "cos".to_string(),
-        vec![Box::new(Number::parse("0"))],
+        vec![Box::new(Number::parse("0").unwrap())],
     );
-    let result = cos_call.accept(&mut interpreter);
+    let result = cos_call.accept(&mut interpreter).unwrap();
     assert_eq!(result, Value::Number(1.0));
 
     // Test sqrt function
     let sqrt_call = FunctionCall::parse(
         "sqrt".to_string(),
-        vec![Box::new(Number::parse("4"))],
+        vec![Box::new(Number::parse("4").unwrap())],
     );
-    let result = sqrt_call.accept(&mut interpreter);
+    let result = sqrt_call.accept(&mut interpreter).unwrap();
     assert_eq!(result, Value::Number(2.0));
 
     // Test abs function
     let abs_call = FunctionCall::parse(
         "abs".to_string(),
-        vec![Box::new(Number::parse("-5"))],
+        vec![Box::new(Number::parse("-5").unwrap())],
     );
-    let result = abs_call.accept(&mut interpreter);
+    let result = abs_call.accept(&mut interpreter).unwrap();
     assert_eq!(result, Value::Number(5.0));
 }
 
@@ -78,9 +78,9 @@ fn test_math_functions_error_handling() {
     // Test sqrt with negative input - should return NaN
     let sqrt_negative = FunctionCall::parse(
         "sqrt".to_string(),
-        vec![Box::new(Number::parse("-1"))],
+        vec![Box::new(Number::parse("-1").unwrap())],
     );
-    let result = sqrt_negative.accept(&mut interpreter);
+    let result = sqrt_negative.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!(val.is_nan()),
         _ => panic!("sqrt(-1) should return NaN"),
@@ -89,9 +89,9 @@ fn test_math_functions_error_handling() {
     // Test log with zero - should return NaN
     let log_zero = FunctionCall::parse(
         "log".to_string(),
-        vec![Box::new(Number::parse("0"))],
+        vec![Box::new(Number::parse("0").unwrap())],
     );
-    let result = log_zero.accept(&mut interpreter);
+    let result = log_zero.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!(val.is_nan()),
         _ => panic!("log(0) should return NaN"),
@@ -100,9 +100,9 @@ fn test_math_functions_error_handling() {
     // Test log with negative input - should return NaN
     let log_negative = FunctionCall::parse(
         "log".to_string(),
-        vec![Box::new(Number::parse("-1"))],
+        vec![Box::new(Number::parse("-1").unwrap())],
     );
-    let result = log_negative.accept(&mut interpreter);
+    let result = log_negative.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!(val.is_nan()),
         _ => panic!("log(-1) should return NaN"),
@@ -111,9 +111,9 @@ fn test_math_functions_error_handling() {
     // Test tan(PI/2) - should return NaN (undefined)
     let tan_pi_over_2 = FunctionCall::parse(
         "tan".to_string(),
-        vec![Box::new(Number::parse("1.5707963267948966"))], // PI/2
+        vec![Box::new(Number::parse("1.5707963267948966").unwrap())], // PI/2
     );
-    let result = tan_pi_over_2.accept(&mut interpreter);
+    let result = tan_pi_over_2.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!(val.is_nan(), "tan(PI/2) should return NaN, got {}", val),
         _ => panic!("tan(PI/2) should return NaN"),
@@ -122,9 +122,9 @@ fn test_math_functions_error_handling() {
     // Test tan(3*PI/2) - should return NaN (undefined)
     let tan_3pi_over_2 = FunctionCall::parse(
         "tan".to_string(),
-        vec![Box::new(Number::parse("4.71238898038469"))], // 3*PI/2
+        vec![Box::new(Number::parse("4.71238898038469").unwrap())], // 3*PI/2
     );
-    let result = tan_3pi_over_2.accept(&mut interpreter);
+    let result = tan_3pi_over_2.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!(val.is_nan(), "tan(3*PI/2) should return NaN, got {}", val),
         _ => panic!("tan(3*PI/2) should return NaN"),
@@ -133,9 +133,9 @@ fn test_math_functions_error_handling() {
     // Test tan(0) - should return 0 (defined)
     let tan_zero = FunctionCall::parse(
         "tan".to_string(),
-        vec![Box::new(Number::parse("0"))],
+        vec![Box::new(Number::parse("0").unwrap())],
     );
-    let result = tan_zero.accept(&mut interpreter);
+    let result = tan_zero.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 0.0).abs() < 1e-10, "tan(0) should return 0, got {}", val),
         _ => panic!("tan(0) should return 0"),
@@ -144,9 +144,9 @@ fn test_math_functions_error_handling() {
     // Test tan(PI) - should return 0 (defined)
     let tan_pi = FunctionCall::parse(
         "tan".to_string(),
-        vec![Box::new(Number::parse("3.141592653589793"))], // PI
+        vec![Box::new(Number::parse("3.141592653589793").unwrap())], // PI
     );
-    let result = tan_pi.accept(&mut interpreter);
+    let result = tan_pi.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 0.0).abs() < 1e-10, "tan(PI) should return 0, got {}", val),
         _ => panic!("tan(PI) should return 0"),
@@ -155,9 +155,9 @@ fn test_math_functions_error_handling() {
     // Test tan(PI/4) - should return 1 (defined)
     let tan_pi_over_4 = FunctionCall::parse(
         "tan".to_string(),
-        vec![Box::new(Number::parse("0.7853981633974483"))], // PI/4
+        vec![Box::new(Number::parse("0.7853981633974483").unwrap())], // PI/4
     );
-    let result = tan_pi_over_4.accept(&mut interpreter);
+    let result = tan_pi_over_4.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 1.0).abs() < 1e-10, "tan(PI/4) should return 1, got {}", val),
         _ => panic!("tan(PI/4) should return 1"),
@@ -176,9 +176,9 @@ fn test_angle_conversion_functions() {
     // Test to_radians function
     let to_radians_call = FunctionCall::parse(
         "to_radians".to_string(),
-        vec![Box::new(Number::parse("180"))],
+        vec![Box::new(Number::parse("180").unwrap())],
     );
-    let result = to_radians_call.accept(&mut interpreter);
+    let result = to_radians_call.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => {
             assert!((val - std::f64::consts::PI).abs() < 1e-10);
@@ -189,9 +189,9 @@ fn test_angle_conversion_functions() {
     // Test to_degrees function
     let to_degrees_call = FunctionCall::parse(
         "to_degrees".to_string(),
-        vec![Box::new(Number::parse(&std::f64::consts::PI.to_string()))],
+        vec![Box::new(Number::parse(&std::f64::consts::PI.to_string()).unwrap())],
     );
-    let result = to_degrees_call.accept(&mut interpreter);
+    let result = to_degrees_call.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => {
             assert!((val - 180.0).abs() < 1e-10);
@@ -211,7 +211,7 @@ fn test_math_constants() {
 
     // Test PI constant
     let pi_var = Var::parse("PI".to_string());
-    let result = pi_var.accept(&mut interpreter);
+    let result = pi_var.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(pi_value) => {
             assert!((pi_value - std::f64::consts::PI).abs() < 1e-10);
@@ -221,7 +221,7 @@ fn test_math_constants() {
 
     // Test E constant
     let e_var = Var::parse("E".to_string());
-    let result = e_var.accept(&mut interpreter);
+    let result = e_var.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(e_value) => {
             assert!((e_value - std::f64::consts::E).abs() < 1e-10);
@@ -240,18 +240,18 @@ fn test_math_function_with_variable() {
     let mut interpreter = Interpreter::new();
 
     // Assign a value to a variable
-    let assignment = Assign::parse(
+    let assignment = Assign::declare(
         "x".to_string(),
-        Box::new(Number::parse("16")),
+        Box::new(Number::parse("16").unwrap()),
     );
-    assignment.accept(&mut interpreter);
+    assignment.accept(&mut interpreter).unwrap();
 
     // Use the variable in a math function
     let sqrt_call = FunctionCall::parse(
         "sqrt".to_string(),
         vec![Box::new(Var::parse("x".to_string()))],
     );
-    let result = sqrt_call.accept(&mut interpreter);
+    let result = sqrt_call.accept(&mut interpreter).unwrap();
     assert_eq!(result, Value::Number(4.0));
 }
 
@@ -261,13 +261,10 @@ fn test_runtime_script_parsing() {
 
     let script_source: String = "./test.oak".to_string();
 
-    if let Err(_) = parse_script(script_source) {
-        println!("Failed to assert the result of file parsing was ok!");
-        std::process::exit(1);
-    } else {
-        println!("File parsing result was ok!");
-        std::process::exit(0);
-    }
+    assert!(
+        parse_script(script_source).is_ok(),
+        "Failed to assert the result of file parsing was ok!"
+    );
 }
 
 #[test]
@@ -282,9 +279,9 @@ fn test_math_functions_edge_cases() {
     // Test sqrt(0) - should return 0
     let sqrt_zero = FunctionCall::parse(
         "sqrt".to_string(),
-        vec![Box::new(Number::parse("0"))],
+        vec![Box::new(Number::parse("0").unwrap())],
     );
-    let result = sqrt_zero.accept(&mut interpreter);
+    let result = sqrt_zero.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 0.0).abs() < 1e-10, "sqrt(0) should return 0, got {}", val),
         _ => panic!("sqrt(0) should return 0"),
@@ -293,9 +290,9 @@ fn test_math_functions_edge_cases() {
     // Test log(1) - should return 0
     let log_one = FunctionCall::parse(
         "log".to_string(),
-        vec![Box::new(Number::parse("1"))],
+        vec![Box::new(Number::parse("1").unwrap())],
     );
-    let result = log_one.accept(&mut interpreter);
+    let result = log_one.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 0.0).abs() < 1e-10, "log(1) should return 0, got {}", val),
         _ => panic!("log(1) should return 0"),
@@ -304,9 +301,9 @@ fn test_math_functions_edge_cases() {
     // Test exp(0) - should return 1
     let exp_zero = FunctionCall::parse(
         "exp".to_string(),
-        vec![Box::new(Number::parse("0"))],
+        vec![Box::new(Number::parse("0").unwrap())],
     );
-    let result = exp_zero.accept(&mut interpreter);
+    let result = exp_zero.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 1.0).abs() < 1e-10, "exp(0) should return 1, got {}", val),
         _ => panic!("exp(0) should return 1"),
@@ -315,9 +312,9 @@ fn test_math_functions_edge_cases() {
     // Test abs(0) - should return 0
     let abs_zero = FunctionCall::parse(
         "abs".to_string(),
-        vec![Box::new(Number::parse("0"))],
+        vec![Box::new(Number::parse("0").unwrap())],
     );
-    let result = abs_zero.accept(&mut interpreter);
+    let result = abs_zero.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 0.0).abs() < 1e-10, "abs(0) should return 0, got {}", val),
         _ => panic!("abs(0) should return 0"),
@@ -326,9 +323,9 @@ fn test_math_functions_edge_cases() {
     // Test abs(-0) - should return 0
     let abs_negative_zero = FunctionCall::parse(
         "abs".to_string(),
-        vec![Box::new(Number::parse("-0"))],
+        vec![Box::new(Number::parse("-0").unwrap())],
     );
-    let result = abs_negative_zero.accept(&mut interpreter);
+    let result = abs_negative_zero.accept(&mut interpreter).unwrap();
     match result {
         Value::Number(val) => assert!((val - 0.0).abs() < 1e-10, "abs(-0) should return 0, got {}", val),
         _ => panic!("abs(-0) should return 0"),
@@ -535,7 +532,7 @@ fn test_calculate_minimum_dead_load_validation() {
 fn test_stability_result_structure() {
     use crate::math::{MathModule, StabilityResult};
 
-    let result = MathModule::verify_building_stability(
+    let result: StabilityResult = MathModule::verify_building_stability(
         5.0,    // dead_load_per_sqm (kN/m²)
         1.0,    // wind_load_per_sqm (kN/m²)
         20.0,   // building_length_a (m)
@@ -742,3 +739,3505 @@ fn test_building_stability_negative_overturning_moment() {
     );
     assert!(result2.is_err());
 }
+
+#[test]
+fn test_tokenize_negative_numbers() {
+    use crate::tokenizer::{tokenize, Token};
+
+    // A leading minus is folded into the number
+    assert_eq!(tokenize("-5"), vec![Token::Integer(-5)]);
+
+    // Binary subtraction keeps the operator separate
+    assert_eq!(
+        tokenize("3-4"),
+        vec![
+            Token::Integer(3),
+            Token::Operator("-".to_string()),
+            Token::Integer(4),
+        ]
+    );
+    assert_eq!(
+        tokenize("3 - 4"),
+        vec![
+            Token::Integer(3),
+            Token::Operator("-".to_string()),
+            Token::Integer(4),
+        ]
+    );
+
+    // A minus right after an assignment is unary
+    assert_eq!(
+        tokenize("x := -2.5"),
+        vec![
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Number(-2.5),
+        ]
+    );
+
+    // A minus right after another operator is unary
+    assert_eq!(
+        tokenize("3 * -2"),
+        vec![
+            Token::Integer(3),
+            Token::Operator("*".to_string()),
+            Token::Integer(-2),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_scientific_notation() {
+    use crate::tokenizer::{tokenize, Token};
+
+    let cases = [("1e10", 1e10), ("2.5E-3", 2.5e-3), ("6.022e23", 6.022e23), ("1e+2", 100.0)];
+    for (source, expected) in cases {
+        let tokens = tokenize(source);
+        assert_eq!(tokens.len(), 1, "{} should be a single token", source);
+        match &tokens[0] {
+            Token::Number(val) => assert!(
+                (val - expected).abs() <= 1e-10 * expected.abs().max(1.0),
+                "{} should parse to {}, got {}",
+                source,
+                expected,
+                val
+            ),
+            other => panic!("{} should be a Number token, got {:?}", source, other),
+        }
+    }
+
+    // Malformed exponents are rejected
+    assert_eq!(tokenize("1e"), vec![Token::Unknown("1e".to_string())]);
+    assert_eq!(tokenize("1e+"), vec![Token::Unknown("1e+".to_string())]);
+}
+
+#[test]
+fn test_tokenize_comments() {
+    use crate::tokenizer::{tokenize, Token};
+
+    // Comment on its own line
+    assert_eq!(
+        tokenize("# hello\nx := 1"),
+        vec![
+            Token::Comment("hello".to_string()),
+            Token::Newline,
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Integer(1),
+        ]
+    );
+
+    // Trailing comment after a statement
+    assert_eq!(
+        tokenize("x := 3 # set x"),
+        vec![
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Integer(3),
+            Token::Comment("set x".to_string()),
+        ]
+    );
+
+    // A `#` inside a string literal is not a comment
+    assert_eq!(
+        tokenize("\"a # b\""),
+        vec![Token::StringLiteral("a # b".to_string())]
+    );
+}
+
+#[test]
+fn test_tokenize_parentheses() {
+    use crate::tokenizer::{tokenize, Token};
+
+    assert_eq!(
+        tokenize("(3 + 4)"),
+        vec![
+            Token::LParen,
+            Token::Integer(3),
+            Token::Operator("+".to_string()),
+            Token::Integer(4),
+            Token::RParen,
+        ]
+    );
+
+    // A minus in front of a group stays an operator
+    assert_eq!(
+        tokenize("-(2)"),
+        vec![
+            Token::Operator("-".to_string()),
+            Token::LParen,
+            Token::Integer(2),
+            Token::RParen,
+        ]
+    );
+
+    // A minus right after an opening paren is unary
+    assert_eq!(
+        tokenize("(-5)"),
+        vec![Token::LParen, Token::Integer(-5), Token::RParen]
+    );
+}
+
+#[test]
+fn test_tokenize_comma_separated_arguments() {
+    use crate::tokenizer::{tokenize, Token};
+
+    assert_eq!(
+        tokenize("max(1, 2, 3)"),
+        vec![
+            Token::Identifier("max".to_string()),
+            Token::LParen,
+            Token::Integer(1),
+            Token::Comma,
+            Token::Integer(2),
+            Token::Comma,
+            Token::Integer(3),
+            Token::RParen,
+        ]
+    );
+
+    // A minus right after a comma is unary
+    assert_eq!(
+        tokenize("min(1, -2)"),
+        vec![
+            Token::Identifier("min".to_string()),
+            Token::LParen,
+            Token::Integer(1),
+            Token::Comma,
+            Token::Integer(-2),
+            Token::RParen,
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_comparison_operators() {
+    use crate::tokenizer::{tokenize, Token};
+
+    let ident = |name: &str| Token::Identifier(name.to_string());
+    let op = |symbol: &str| Token::Operator(symbol.to_string());
+
+    assert_eq!(tokenize("a >= b"), vec![ident("a"), op(">="), ident("b")]);
+    assert_eq!(tokenize("a>=b"), vec![ident("a"), op(">="), ident("b")]);
+    assert_eq!(tokenize("a > b"), vec![ident("a"), op(">"), ident("b")]);
+
+    for symbol in ["==", "!=", "<", "<=", ">", ">="] {
+        assert_eq!(
+            tokenize(&format!("a {} b", symbol)),
+            vec![ident("a"), op(symbol), ident("b")]
+        );
+    }
+
+    // A lone `=` is neither an assignment nor a comparison
+    assert_eq!(
+        tokenize("a = b"),
+        vec![ident("a"), Token::Unknown("=".to_string()), ident("b")]
+    );
+}
+
+#[test]
+fn test_parse_expr_precedence() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+
+    // `*` binds tighter than `+`
+    assert_eq!(evaluate("2 + 3 * 4"), Value::Number(14.0));
+    // Parentheses override precedence
+    assert_eq!(evaluate("(2 + 3) * 4"), Value::Number(20.0));
+    // Operators of equal precedence are left-associative
+    assert_eq!(evaluate("10 - 4 - 3"), Value::Number(3.0));
+    assert_eq!(evaluate("8 / 4 / 2"), Value::Number(1.0));
+    // Function calls are primary expressions
+    assert_eq!(evaluate("sqrt(16) + 1"), Value::Number(5.0));
+}
+
+#[test]
+fn test_parse_expr_errors() {
+    use crate::{parser::parse_expr, tokenizer::tokenize};
+
+    assert!(parse_expr(&tokenize("(2 + 3")).is_err());
+    assert!(parse_expr(&tokenize("2 +")).is_err());
+    assert!(parse_expr(&tokenize("2 3")).is_err());
+    assert!(parse_expr(&tokenize("")).is_err());
+}
+
+#[test]
+fn test_modulo_and_power_operators() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, BinOp, Node, Number, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+
+    let modulo = BinOp::parse(
+        Box::new(Number::parse("10").unwrap()),
+        "%".to_string(),
+        Box::new(Number::parse("3").unwrap()),
+    );
+    assert_eq!(modulo.accept(&mut interpreter).unwrap(), Value::Number(1.0));
+
+    let power = BinOp::parse(
+        Box::new(Number::parse("2").unwrap()),
+        "^".to_string(),
+        Box::new(Number::parse("10").unwrap()),
+    );
+    assert_eq!(power.accept(&mut interpreter).unwrap(), Value::Number(1024.0));
+
+    match parse_expr(&tokenize("2 ^ 0.5")).unwrap().accept(&mut interpreter).unwrap() {
+        Value::Number(val) => assert!((val - std::f64::consts::SQRT_2).abs() < 1e-10),
+        other => panic!("2 ^ 0.5 should be a number, got {:?}", other),
+    }
+
+    // `^` binds tighter than `*`, and `%` as tight as `*`
+    assert_eq!(
+        parse_expr(&tokenize("3 * 2 ^ 2")).unwrap().accept(&mut interpreter).unwrap(),
+        Value::Number(12.0)
+    );
+    assert_eq!(
+        parse_expr(&tokenize("1 + 7 % 4")).unwrap().accept(&mut interpreter).unwrap(),
+        Value::Number(4.0)
+    );
+
+    // Remainder by zero is an error for floats and integers alike
+    assert_eq!(
+        parse_expr(&tokenize("5.0 % 0")).unwrap().accept(&mut interpreter),
+        Err(crate::interpreter::RuntimeError::DivisionByZero)
+    );
+    assert_eq!(
+        parse_expr(&tokenize("5 % 0")).unwrap().accept(&mut interpreter),
+        Err(crate::interpreter::RuntimeError::DivisionByZero)
+    );
+}
+
+#[test]
+fn test_unknown_function_is_an_error() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{FunctionCall, Node, Var},
+    };
+
+    let mut interpreter = Interpreter::new();
+
+    // Calling a function that does not exist is an error, not None
+    let call = FunctionCall::parse("nope".to_string(), vec![]);
+    assert_eq!(
+        call.accept(&mut interpreter),
+        Err(RuntimeError::UnknownFunction("nope".to_string()))
+    );
+
+    // Reported before the arguments are evaluated
+    let call = FunctionCall::parse("sqr".to_string(), vec![Box::new(Var::parse("missing".to_string()))]);
+    assert_eq!(call.accept(&mut interpreter).unwrap_err().to_string(), "Unknown function 'sqr'");
+}
+
+#[test]
+fn test_visitor_returns_runtime_errors() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{BinOp, FunctionCall, Node, Number, StringLiteral, Var},
+    };
+
+    let mut interpreter = Interpreter::new();
+
+    // Undefined variables are errors, not None
+    let result = Var::parse("missing".to_string()).accept(&mut interpreter);
+    assert_eq!(
+        result,
+        Err(RuntimeError::UndefinedVariable("missing".to_string()))
+    );
+
+    // Mixing a string and a number in arithmetic is a type mismatch
+    let mixed = BinOp::parse(
+        Box::new(Number::parse("1").unwrap()),
+        "+".to_string(),
+        Box::new(StringLiteral::parse("a".to_string())),
+    );
+    assert_eq!(
+        mixed.accept(&mut interpreter),
+        Err(RuntimeError::TypeMismatch { op: "+".to_string() })
+    );
+
+    // Unknown operators are reported by name
+    let unknown = BinOp::parse(
+        Box::new(Number::parse("1").unwrap()),
+        "&".to_string(),
+        Box::new(Number::parse("2").unwrap()),
+    );
+    assert_eq!(
+        unknown.accept(&mut interpreter),
+        Err(RuntimeError::UnknownOperator("&".to_string()))
+    );
+
+    // Calling a math function with the wrong number of arguments
+    let sqrt_call = FunctionCall::parse(
+        "sqrt".to_string(),
+        vec![Box::new(Number::parse("1").unwrap()), Box::new(Number::parse("2").unwrap())],
+    );
+    assert_eq!(
+        sqrt_call.accept(&mut interpreter),
+        Err(RuntimeError::WrongArity {
+            func: "sqrt".to_string(),
+            expected: 1,
+            got: 2,
+        })
+    );
+
+    // Errors in nested expressions propagate to the caller
+    let nested = BinOp::parse(
+        Box::new(Number::parse("1").unwrap()),
+        "+".to_string(),
+        Box::new(Var::parse("missing".to_string())),
+    );
+    assert!(nested.accept(&mut interpreter).is_err());
+}
+
+#[test]
+fn test_binary_math_functions() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, FunctionCall, Node, Number, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    assert_eq!(evaluate("pow(2, 10)"), Ok(Value::Number(1024.0)));
+    assert_eq!(evaluate("min(3, -7)"), Ok(Value::Number(-7.0)));
+    assert_eq!(evaluate("max(3, -7)"), Ok(Value::Number(3.0)));
+    match evaluate("atan2(1, 1)") {
+        Ok(Value::Number(val)) => assert!((val - std::f64::consts::FRAC_PI_4).abs() < 1e-10),
+        other => panic!("atan2(1, 1) should be PI/4, got {:?}", other),
+    }
+    match evaluate("atan2(1, -1)") {
+        Ok(Value::Number(val)) => assert!((val - 3.0 * std::f64::consts::FRAC_PI_4).abs() < 1e-10),
+        other => panic!("atan2(1, -1) should be 3*PI/4, got {:?}", other),
+    }
+
+    // Arity mismatches are reported with the expected count
+    let pow_call = FunctionCall::parse("pow".to_string(), vec![Box::new(Number::parse("2").unwrap())]);
+    assert_eq!(
+        pow_call.accept(&mut Interpreter::new()),
+        Err(RuntimeError::WrongArity {
+            func: "pow".to_string(),
+            expected: 2,
+            got: 1,
+        })
+    );
+}
+
+#[test]
+fn test_rounding_functions() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{FunctionCall, Node, Number, Value},
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut call = |name: &str, arg: &str| {
+        FunctionCall::parse(name.to_string(), vec![Box::new(Number::parse(arg).unwrap())])
+            .accept(&mut interpreter)
+            .unwrap()
+    };
+
+    assert_eq!(call("floor", "2.7"), Value::Number(2.0));
+    assert_eq!(call("ceil", "2.1"), Value::Number(3.0));
+    assert_eq!(call("round", "2.5"), Value::Number(3.0));
+    assert_eq!(call("round", "-2.5"), Value::Number(-3.0));
+    assert_eq!(call("trunc", "-2.7"), Value::Number(-2.0));
+}
+
+#[test]
+fn test_inverse_trigonometric_functions() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{FunctionCall, Node, Number, Value},
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut call = |name: &str, arg: &str| {
+        match FunctionCall::parse(name.to_string(), vec![Box::new(Number::parse(arg).unwrap())])
+            .accept(&mut interpreter)
+            .unwrap()
+        {
+            Value::Number(val) => val,
+            other => panic!("{}({}) should return a number, got {:?}", name, arg, other),
+        }
+    };
+
+    assert!((call("asin", "1") - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+    assert!((call("acos", "1") - 0.0).abs() < 1e-10);
+    assert!((call("atan", "1") - std::f64::consts::FRAC_PI_4).abs() < 1e-10);
+
+    // Outside [-1, 1] asin and acos are undefined
+    assert!(call("asin", "2").is_nan());
+    assert!(call("acos", "-1.5").is_nan());
+
+    // atan is defined everywhere
+    assert!(call("atan", "1e300").is_finite());
+}
+
+#[test]
+fn test_logarithm_base_functions() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| match parse_expr(&tokenize(source))
+        .unwrap()
+        .accept(&mut interpreter)
+        .unwrap()
+    {
+        Value::Number(val) => val,
+        other => panic!("{} should return a number, got {:?}", source, other),
+    };
+
+    assert!((evaluate("log10(1000)") - 3.0).abs() < 1e-10);
+    assert!((evaluate("log2(8)") - 3.0).abs() < 1e-10);
+    assert!((evaluate("log_base(27, 3)") - 3.0).abs() < 1e-10);
+
+    // Invalid inputs are NaN
+    assert!(evaluate("log10(0)").is_nan());
+    assert!(evaluate("log2(-4)").is_nan());
+    assert!(evaluate("log_base(8, 1)").is_nan());
+    assert!(evaluate("log_base(8, -2)").is_nan());
+    assert!(evaluate("log_base(-8, 2)").is_nan());
+}
+
+#[test]
+fn test_calc_architecture_command() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult};
+
+    let output = calc_architecture_command("stability", &[5.0, 1.0, 20.0, 15.0, 30.0, 10.0, 15.0]);
+    assert!(output.contains("The building is stable"), "{}", output);
+
+    let output = calc_architecture_command("stability", &[1.0, 5.0, 10.0, 10.0, 20.0, 5.0, 10.0]);
+    assert!(output.contains("The building is unstable"), "{}", output);
+
+    match calc_architecture("minimum_dead_load", &[2.0, 20.0, 15.0, 30.0, 8.0, 15.0, 3.0]) {
+        Ok(ArchitecturalResult::MinimumDeadLoad(dead_load)) => assert!(dead_load > 0.0),
+        other => panic!("Expected a minimum dead load, got {:?}", other),
+    }
+
+    // Failures are reported in the output text
+    let output = calc_architecture_command("stability", &[5.0, 1.0]);
+    assert!(output.starts_with("Error:"), "{}", output);
+    assert!(output.contains("expects 7 to 8 parameters, got 2"), "{}", output);
+
+    let output = calc_architecture_command("stability", &[5.0, 1.0, 20.0, 15.0, 30.0, 2.5, 15.0]);
+    assert!(output.contains("Number of floors must be a non-negative whole number"), "{}", output);
+
+    let output = calc_architecture_command("teleport", &[]);
+    assert!(output.contains("Unknown architectural calculation 'teleport'"), "{}", output);
+}
+
+#[test]
+fn test_calc_architecture_from_interpreter() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+
+    let call = parse_expr(&tokenize(
+        "calc_architecture(\"stability\", 5, 1, 20, 15, 30, 10, 30 / 2)",
+    ))
+    .unwrap();
+    match call.accept(&mut interpreter).unwrap() {
+        Value::String(output) => assert!(output.contains("stable"), "{}", output),
+        other => panic!("calc_architecture should return a string, got {:?}", other),
+    }
+
+    // The calculation name must be a string
+    let call = parse_expr(&tokenize("calc_architecture(1, 2)")).unwrap();
+    assert_eq!(
+        call.accept(&mut interpreter),
+        Err(RuntimeError::TypeMismatch {
+            op: "calc_architecture".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_repl_evaluates_lines() {
+    use crate::{parser::Value, repl::Repl};
+
+    // Variables persist between lines
+    let mut repl = Repl::new();
+    assert_eq!(repl.eval_line("var x := 3 + 4").unwrap(), Value::Number(7.0));
+    assert_eq!(repl.eval_line("x").unwrap(), Value::Number(7.0));
+    assert_eq!(repl.eval_line("var y := x * 2").unwrap(), Value::Number(14.0));
+
+    // Feed a whole session through the loop
+    let input = "var x := 3 + 4\nx\nundefined_thing\n(1 + 2))\nx - 2\nexit\nx\n";
+    let mut output = Vec::new();
+    Repl::new().run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().map(|line| line.trim_start_matches("> ")).collect();
+
+    assert_eq!(lines[0], "7");
+    assert_eq!(lines[1], "7");
+    // Errors are reported and the loop keeps going
+    assert!(lines[2].starts_with("Error:") && lines[2].contains("undefined_thing"));
+    assert!(lines[3].starts_with("Error:"));
+    assert_eq!(lines[4], "5");
+    // Nothing after `exit` is evaluated
+    assert_eq!(lines.len(), 6);
+}
+
+#[test]
+fn test_repl_vars_and_clear_commands() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{Assign, Node, Number, Value},
+        repl::Repl,
+    };
+
+    let mut interpreter = Interpreter::new();
+    Assign::declare("b".to_string(), Box::new(Number::parse("2").unwrap()))
+        .accept(&mut interpreter)
+        .unwrap();
+    assert_eq!(interpreter.variables().get("b"), Some(&Value::Number(2.0)));
+    interpreter.clear_variables();
+    assert!(interpreter.variables().is_empty());
+
+    let input = "var zeta := 1\nvar alpha := 2.5\n:vars\n:clear\n:vars\nalpha\n:bogus\n";
+    let mut output = Vec::new();
+    Repl::new().run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().map(|line| line.trim_start_matches("> ")).collect();
+
+    // Sorted by name
+    assert_eq!(lines[2], "alpha = 2.5");
+    assert_eq!(lines[3], "zeta = 1");
+    assert_eq!(lines[4], "Variables cleared");
+    // Nothing is listed after clearing, and the variable is gone
+    assert!(lines[5].starts_with("Error:") && lines[5].contains("alpha"));
+    assert_eq!(lines[6], "Unknown command ':bogus'");
+}
+
+#[test]
+fn test_repl_history_file() {
+    use crate::repl::{history_path, Repl};
+    use std::fs;
+
+    let path = std::env::temp_dir().join(format!("oak_history_test_{}", std::process::id()));
+    fs::write(&path, "old := 1\n").unwrap();
+
+    let mut repl = Repl::new().with_history_file(path.clone());
+    // Lines from previous sessions are loaded
+    assert_eq!(repl.history(), ["old := 1".to_string()]);
+
+    let mut output = Vec::new();
+    repl.run("x := 2\n\n:history\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    // `:history` lists this session's lines only, with indices
+    assert!(output.contains("   1  x := 2"), "{}", output);
+    assert!(output.contains("   2  :history"), "{}", output);
+    assert!(!output.contains("old := 1"), "{}", output);
+
+    // Entered lines are appended to the file
+    let saved = fs::read_to_string(&path).unwrap();
+    assert_eq!(saved, "old := 1\nx := 2\n:history\n");
+    fs::remove_file(&path).unwrap();
+
+    // An unwritable location is handled gracefully
+    let missing = std::env::temp_dir().join("oak_missing_dir").join("nested").join("history");
+    let mut repl = Repl::new().with_history_file(missing);
+    let mut output = Vec::new();
+    repl.run("var y := 3\ny\n".as_bytes(), &mut output).unwrap();
+    assert!(String::from_utf8(output).unwrap().contains('3'));
+    assert_eq!(repl.history().len(), 2);
+
+    // The location can be configured through OAK_HISTORY
+    std::env::set_var("OAK_HISTORY", "/tmp/custom_oak_history");
+    assert_eq!(history_path(), Some(std::path::PathBuf::from("/tmp/custom_oak_history")));
+    std::env::remove_var("OAK_HISTORY");
+}
+
+#[test]
+fn test_line_breaks_end_statements() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_statements, Value},
+        tokenizer::{detokenize, tokenize},
+    };
+
+    let run = |source: &str| {
+        let mut interpreter = Interpreter::new();
+        for statement in parse_statements(&tokenize(source)).unwrap() {
+            statement.accept(&mut interpreter).unwrap();
+        }
+        interpreter.variables().clone()
+    };
+    let count = |source: &str| parse_statements(&tokenize(source)).unwrap().len();
+
+    // A line starting with `-` is a statement of its own, not a subtraction
+    assert_eq!(run("var x := 10\n-5\n")["x"], Value::Integer(10));
+    assert_eq!(count("var x := 10\n-5"), 2);
+    assert_eq!(count("var x := 10\n- 5"), 2);
+
+    // A line starting with `(` is not a call on the previous line
+    assert_eq!(run("var y := 2\nvar x := y\n(3)\n")["x"], Value::Integer(2));
+    assert_eq!(count("var x := y\n(3)"), 2);
+
+    // A trailing operator or open parentheses continue onto the next line
+    assert_eq!(run("var x := 10 -\n5")["x"], Value::Integer(5));
+    assert_eq!(run("var x := (10\n- 5)")["x"], Value::Integer(5));
+    assert_eq!(run("var x := max(10,\n20\n, 30)")["x"], Value::Integer(30));
+    assert_eq!(run("var x := 1 > 2 ?\n1 :\n2\n")["x"], Value::Integer(2));
+    assert_eq!(run("var x := if 1 < 2\nthen 3\nelse 4")["x"], Value::Integer(3));
+    assert_eq!(run("var x := 1 + # one\n2")["x"], Value::Integer(3));
+    assert_eq!(run("var x := 1 # one\n-2")["x"], Value::Integer(1));
+
+    // Statements on the same line must be separated by `;`
+    assert!(parse_statements(&tokenize("var x := 1 2")).is_err());
+    assert!(parse_statements(&tokenize("var x := 1\nvar y := x 3")).is_err());
+    assert!(parse_statements(&tokenize("var x := 1)")).is_err());
+    assert_eq!(count("var x := 1; 2 # two"), 3);
+
+    // Line breaks survive a round trip
+    assert_eq!(detokenize(&tokenize("x := 1\n-5\n# c\ny")), "x := 1\n-5\n# c\ny");
+}
+
+#[test]
+fn test_runtime_executes_script() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{ScriptError, Value},
+        runtime::{run, run_with_interpreter},
+    };
+    use std::fs;
+
+    let path = std::env::temp_dir().join(format!("oak_runtime_test_{}.oak", std::process::id()));
+    fs::write(&path, "var x := 3\nvar y := x * 2 + 1\n").unwrap();
+
+    let mut interpreter = Interpreter::new();
+    run_with_interpreter(path.to_string_lossy().to_string(), &mut interpreter).unwrap();
+    assert_eq!(interpreter.variables().get("x"), Some(&Value::Integer(3)));
+    assert_eq!(interpreter.variables().get("y"), Some(&Value::Integer(7)));
+
+    // Runtime errors are propagated through run
+    fs::write(&path, "var x := 3\nvar y := z + 1\n").unwrap();
+    match run(path.to_string_lossy().to_string()) {
+        Err(ScriptError::Runtime(RuntimeError::UndefinedVariable(name))) => assert_eq!(name, "z"),
+        other => panic!("Expected an undefined variable error, got {:?}", other),
+    }
+    fs::remove_file(&path).unwrap();
+
+    // And so are I/O errors
+    assert!(matches!(
+        run("./does_not_exist.oak".to_string()),
+        Err(ScriptError::Io(_))
+    ));
+}
+
+#[test]
+fn test_runtime_executes_source_string() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_str, ScriptError, Value},
+        runtime::{run_source, run_source_with_interpreter},
+    };
+
+    assert_eq!(parse_str("x := 1\ny := x + 1").unwrap().len(), 2);
+
+    let mut interpreter = Interpreter::new();
+    run_source_with_interpreter("var x := 3\nvar y := x * 2 + 1\nvar z := max(x, y)\n", &mut interpreter).unwrap();
+    assert_eq!(interpreter.variables().get("x"), Some(&Value::Integer(3)));
+    assert_eq!(interpreter.variables().get("y"), Some(&Value::Integer(7)));
+    assert_eq!(interpreter.variables().get("z"), Some(&Value::Number(7.0)));
+
+    assert!(run_source("").is_ok());
+    match run_source("var x := 3\nvar y := z + 1") {
+        Err(ScriptError::Runtime(RuntimeError::UndefinedVariable(name))) => assert_eq!(name, "z"),
+        other => panic!("Expected an undefined variable error, got {:?}", other),
+    }
+    assert!(matches!(run_source("x := 1 $ 2"), Err(ScriptError::Lex(_))));
+}
+
+#[test]
+fn test_run_with_output() {
+    use crate::{interpreter::Interpreter, parser::Value, runtime::run_with_output};
+
+    let path = std::env::temp_dir().join("oak_run_with_output.oak");
+    std::fs::write(&path, "var x := 3\nx * 2\n").unwrap();
+    let mut output = Vec::new();
+    let result = run_with_output(path.to_string_lossy().into_owned(), &mut Interpreter::new(), &mut output);
+    assert_eq!(result.unwrap(), Value::Number(6.0));
+    // Every statement with a value is written, in order
+    assert_eq!(String::from_utf8(output).unwrap(), "3\n6\n");
+
+    // Values written before a failing statement are kept
+    std::fs::write(&path, "var x := 3\nmissing + 1\nx\n").unwrap();
+    let mut output = Vec::new();
+    let result = run_with_output(path.to_string_lossy().into_owned(), &mut Interpreter::new(), &mut output);
+    assert!(result.is_err());
+    assert_eq!(String::from_utf8(output).unwrap(), "3\n");
+}
+
+#[test]
+fn test_runtime_returns_last_value() {
+    use crate::{
+        parser::Value,
+        runtime::{run_source, run_with_result},
+    };
+    use std::fs;
+
+    assert_eq!(run_source("var x := 5\nx * 2").unwrap(), Value::Number(10.0));
+    assert_eq!(run_source("").unwrap(), Value::None);
+    // Assignments evaluate to the assigned value
+    assert_eq!(run_source("var y := 2 ^ 3").unwrap(), Value::Number(8.0));
+
+    let path = std::env::temp_dir().join(format!("oak_result_test_{}.oak", std::process::id()));
+    fs::write(&path, "var x := 5\nx * 2\n").unwrap();
+    assert_eq!(
+        run_with_result(path.to_string_lossy().to_string()).unwrap(),
+        Value::Number(10.0)
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_comparison_operators() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, BinOp, Node, Number, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+
+    let less = BinOp::parse(
+        Box::new(Number::parse("3").unwrap()),
+        "<".to_string(),
+        Box::new(Number::parse("4").unwrap()),
+    );
+    assert_eq!(less.accept(&mut interpreter).unwrap(), Value::Bool(true));
+
+    let equal = BinOp::parse(
+        Box::new(Number::parse("3").unwrap()),
+        "==".to_string(),
+        Box::new(Number::parse("4").unwrap()),
+    );
+    assert_eq!(equal.accept(&mut interpreter).unwrap(), Value::Bool(false));
+
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(evaluate("3 != 4"), Value::Bool(true));
+    assert_eq!(evaluate("4 <= 4"), Value::Bool(true));
+    assert_eq!(evaluate("3 > 4"), Value::Bool(false));
+    assert_eq!(evaluate("3 >= 4"), Value::Bool(false));
+    // Comparisons bind looser than arithmetic
+    assert_eq!(evaluate("1 + 2 == 3"), Value::Bool(true));
+    assert_eq!(evaluate("2 * 3 > 5"), Value::Bool(true));
+    assert_eq!(format!("{}", evaluate("1 < 2")), "true");
+}
+
+#[test]
+fn test_undefined_variable_is_an_error() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Assign, Node, Var},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+
+    // Assigning from an unknown variable fails and leaves the target unassigned
+    let assignment = Assign::parse(
+        "y".to_string(),
+        Box::new(Var::parse("undefined_thing".to_string())),
+    );
+    assert_eq!(
+        assignment.accept(&mut interpreter),
+        Err(RuntimeError::UndefinedVariable("undefined_thing".to_string()))
+    );
+    assert!(!interpreter.variables().contains_key("y"));
+
+    // The same goes for an unknown variable deep inside an expression
+    let expr = parse_expr(&tokenize("y := sqrt(1 + missing * 2)")).unwrap();
+    assert_eq!(
+        expr.accept(&mut interpreter),
+        Err(RuntimeError::UndefinedVariable("missing".to_string()))
+    );
+    assert!(!interpreter.variables().contains_key("y"));
+}
+
+#[test]
+fn test_number_parse_rejects_malformed_literals() {
+    use crate::parser::{parse_expr, Number, ScriptError};
+    use crate::tokenizer::tokenize;
+
+    assert_eq!(Number::parse("2.5").unwrap().value, 2.5);
+
+    for literal in ["1.2.3", "", "abc", "1e"] {
+        match Number::parse(literal) {
+            Err(ScriptError::Parse(message)) => assert!(message.contains("Invalid number literal")),
+            Err(other) => panic!("Unexpected error for '{}': {:?}", literal, other),
+            Ok(_) => panic!("'{}' should not parse as a number", literal),
+        }
+    }
+
+    // A malformed literal in source is a parse error rather than a crash
+    assert!(parse_expr(&tokenize("x := 1.2.3")).is_err());
+}
+
+#[test]
+fn test_factorial() {
+    use crate::{
+        interpreter::Interpreter,
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    assert_eq!(MathModule::factorial(5.0), 120.0);
+    assert_eq!(MathModule::factorial(0.0), 1.0);
+    assert_eq!(MathModule::factorial(1.0), 1.0);
+    assert!(MathModule::factorial(-1.0).is_nan());
+    assert!(MathModule::factorial(2.5).is_nan());
+
+    // 170! is the largest factorial representable as f64
+    assert!(MathModule::factorial(170.0).is_finite());
+    assert_eq!(MathModule::factorial(171.0), f64::INFINITY);
+
+    let mut interpreter = Interpreter::new();
+    let result = parse_expr(&tokenize("factorial(5)")).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(result, Value::Number(120.0));
+}
+
+#[test]
+fn test_gcd_and_lcm() {
+    use crate::{
+        interpreter::Interpreter,
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    assert_eq!(MathModule::gcd(12.0, 18.0), 6.0);
+    assert_eq!(MathModule::gcd(-12.0, 18.0), 6.0);
+    assert_eq!(MathModule::gcd(0.0, 5.0), 5.0);
+    assert_eq!(MathModule::lcm(4.0, 6.0), 12.0);
+    assert_eq!(MathModule::lcm(0.0, 6.0), 0.0);
+    assert!(MathModule::gcd(12.5, 3.0).is_nan());
+    assert!(MathModule::lcm(4.0, 0.5).is_nan());
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(evaluate("gcd(12, 18)"), Value::Number(6.0));
+    assert_eq!(evaluate("lcm(4, 6)"), Value::Number(12.0));
+}
+
+#[test]
+fn test_clamp() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    // In range
+    assert_eq!(evaluate("clamp(5, 0, 10)"), Ok(Value::Number(5.0)));
+    // Below range
+    assert_eq!(evaluate("clamp(-3, 0, 10)"), Ok(Value::Number(0.0)));
+    // Above range
+    assert_eq!(evaluate("clamp(42, 0, 10)"), Ok(Value::Number(10.0)));
+    // Inverted bounds
+    assert!(MathModule::clamp(5.0, 10.0, 0.0).is_nan());
+
+    assert_eq!(
+        evaluate("clamp(1, 2)"),
+        Err(RuntimeError::WrongArity {
+            func: "clamp".to_string(),
+            expected: 3,
+            got: 2,
+        })
+    );
+}
+
+#[test]
+fn test_seismic_base_shear() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule};
+
+    // Zone 4 (Z = 0.4), standard occupancy (I = 1.0), special moment frame (R = 8)
+    let result = MathModule::calculate_seismic_base_shear(0.4, 1.0, 8.0, 12000.0).unwrap();
+    assert!((result.seismic_coefficient - 0.05).abs() < 1e-12);
+    assert!((result.base_shear - 600.0).abs() < 1e-9);
+
+    // Essential facility raises the base shear
+    let essential = MathModule::calculate_seismic_base_shear(0.4, 1.5, 8.0, 12000.0).unwrap();
+    assert!((essential.base_shear - 900.0).abs() < 1e-9);
+
+    match calc_architecture("seismic", &[0.4, 1.0, 8.0, 12000.0]) {
+        Ok(ArchitecturalResult::Seismic(result)) => assert!((result.base_shear - 600.0).abs() < 1e-9),
+        other => panic!("Expected a seismic result, got {:?}", other),
+    }
+
+    let output = calc_architecture_command("seismic", &[0.4, 1.0, 8.0, 12000.0]);
+    assert!(output.contains("Base shear (V): 600.00 kN"), "{}", output);
+
+    // Validation errors
+    assert!(MathModule::calculate_seismic_base_shear(0.0, 1.0, 8.0, 12000.0).is_err());
+    assert!(MathModule::calculate_seismic_base_shear(0.4, -1.0, 8.0, 12000.0).is_err());
+    assert!(MathModule::calculate_seismic_base_shear(0.4, 1.0, f64::INFINITY, 12000.0).is_err());
+    assert!(MathModule::calculate_seismic_base_shear(0.4, 1.0, 8.0, f64::NAN).is_err());
+    let output = calc_architecture_command("seismic", &[0.4, 1.0, 0.0, 12000.0]);
+    assert!(output.contains("Response modification factor must be positive and finite"), "{}", output);
+    let output = calc_architecture_command("seismic", &[0.4, 1.0]);
+    assert!(output.contains("expects 4 parameters, got 2"), "{}", output);
+}
+
+#[test]
+fn test_beam_deflection() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule};
+
+    // w = 10 kN/m, L = 6 m, E = 200 GPa (2e8 kPa), I = 8e-5 m⁴
+    // δ = 5 * 10 * 6⁴ / (384 * 2e8 * 8e-5) = 64800 / 6144000 = 0.010546875 m
+    let result = MathModule::calculate_beam_deflection(10.0, 6.0, 2e8, 8e-5).unwrap();
+    assert!((result.deflection - 0.010546875).abs() < 1e-12);
+    assert!((result.span_ratio - 6.0 / 0.010546875).abs() < 1e-6);
+
+    match calc_architecture("beam_deflection", &[10.0, 6.0, 2e8, 8e-5]) {
+        Ok(ArchitecturalResult::BeamDeflection(result)) => {
+            assert!((result.deflection - 0.010546875).abs() < 1e-12)
+        }
+        other => panic!("Expected a beam deflection, got {:?}", other),
+    }
+
+    let output = calc_architecture_command("beam_deflection", &[10.0, 6.0, 2e8, 8e-5]);
+    assert!(output.contains("Maximum deflection: 10.55 mm"), "{}", output);
+    assert!(output.contains("L/569"), "{}", output);
+
+    // Validation errors
+    assert!(MathModule::calculate_beam_deflection(0.0, 6.0, 2e8, 8e-5).is_err());
+    assert!(MathModule::calculate_beam_deflection(10.0, -6.0, 2e8, 8e-5).is_err());
+    assert!(MathModule::calculate_beam_deflection(10.0, 6.0, f64::NAN, 8e-5).is_err());
+    let output = calc_architecture_command("beam_deflection", &[10.0, 6.0, 2e8, 0.0]);
+    assert!(output.contains("Moment of inertia must be positive and finite"), "{}", output);
+}
+
+#[test]
+fn test_wind_stiffness_compliance() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule};
+
+    // 10 x 40 gives a ratio of 0.25, compliant with the default 1/5 limit
+    let result = MathModule::check_wind_stiffness_compliance(10.0, 40.0).unwrap();
+    assert!((result.slenderness_ratio - 0.25).abs() < 1e-12);
+    assert_eq!(result.min_ratio, 0.2);
+    assert!(result.is_compliant);
+
+    // A stricter 0.3 limit flips the same building to non-compliant
+    let result = MathModule::check_wind_stiffness_compliance_with_limit(10.0, 40.0, 0.3).unwrap();
+    assert!(!result.is_compliant);
+
+    match calc_architecture("wind_stiffness", &[10.0, 40.0]) {
+        Ok(ArchitecturalResult::WindStiffness(result)) => assert!(result.is_compliant),
+        other => panic!("Expected a wind stiffness result, got {:?}", other),
+    }
+    match calc_architecture("wind_stiffness", &[10.0, 40.0, 0.3]) {
+        Ok(ArchitecturalResult::WindStiffness(result)) => assert!(!result.is_compliant),
+        other => panic!("Expected a wind stiffness result, got {:?}", other),
+    }
+    let output = calc_architecture_command("wind_stiffness", &[10.0, 40.0, 0.3]);
+    assert!(output.contains("The building is not compliant"), "{}", output);
+
+    // The limit must lie in (0, 1)
+    assert!(MathModule::check_wind_stiffness_compliance_with_limit(10.0, 40.0, 0.0).is_err());
+    assert!(MathModule::check_wind_stiffness_compliance_with_limit(10.0, 40.0, 1.0).is_err());
+    assert!(MathModule::check_wind_stiffness_compliance_with_limit(10.0, 40.0, f64::NAN).is_err());
+    let output = calc_architecture_command("wind_stiffness", &[10.0]);
+    assert!(output.contains("expects 2 to 3 parameters, got 1"), "{}", output);
+}
+
+#[test]
+fn test_stability_with_custom_safety_factor() {
+    use crate::math::{calc_architecture, ArchitecturalResult, MathModule};
+
+    // a = 6, b = 8: G = 3.125 * 48 = 150 kN, da = 5 m, Me = 750 kN·m
+    // W = 1 * 10 * 6 = 60 kN, d = 5 m, Mv = 300 kN·m, Me/Mv = 2.5
+    let params = (3.125, 1.0, 6.0, 8.0, 10.0, 1, 5.0);
+
+    let lenient = MathModule::verify_building_stability_with_factor(
+        params.0, params.1, params.2, params.3, params.4, params.5, params.6, 2.0,
+    )
+    .unwrap();
+    assert!((lenient.stability_ratio - 2.5).abs() < 1e-12);
+    assert!(lenient.is_stable);
+    assert!((lenient.safety_margin - 0.5).abs() < 1e-12);
+    assert_eq!(lenient.safety_factor, 2.0);
+
+    // The default factor of 3.0 rejects the same building
+    let default = MathModule::verify_building_stability(
+        params.0, params.1, params.2, params.3, params.4, params.5, params.6,
+    )
+    .unwrap();
+    assert!(!default.is_stable);
+    assert!((default.safety_margin + 0.5).abs() < 1e-12);
+    assert_eq!(default.safety_factor, 3.0);
+
+    // The optional 8th parameter of calc_architecture sets the factor
+    match calc_architecture("stability", &[3.125, 1.0, 6.0, 8.0, 10.0, 1.0, 5.0, 2.0]) {
+        Ok(ArchitecturalResult::Stability(result)) => assert!(result.is_stable),
+        other => panic!("Expected a stability result, got {:?}", other),
+    }
+    match calc_architecture("stability", &[3.125, 1.0, 6.0, 8.0, 10.0, 1.0, 5.0]) {
+        Ok(ArchitecturalResult::Stability(result)) => assert!(!result.is_stable),
+        other => panic!("Expected a stability result, got {:?}", other),
+    }
+
+    assert!(MathModule::verify_building_stability_with_factor(
+        params.0, params.1, params.2, params.3, params.4, params.5, params.6, 0.0,
+    )
+    .is_err());
+}
+
+#[test]
+fn test_value_approx_eq() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Value, DEFAULT_EPSILON},
+        tokenizer::tokenize,
+    };
+
+    let sum = Value::Number(0.1 + 0.2);
+    assert_ne!(sum, Value::Number(0.3));
+    assert!(sum.approx_eq(&Value::Number(0.3), DEFAULT_EPSILON));
+    assert!(!Value::Number(1.0).approx_eq(&Value::Number(1.001), DEFAULT_EPSILON));
+    assert!(Value::Number(1.0).approx_eq(&Value::Number(1.001), 0.01));
+
+    // Tolerance is relative for large magnitudes
+    assert!(Value::Number(1e12).approx_eq(&Value::Number(1e12 + 1e-3), DEFAULT_EPSILON));
+
+    // Non-finite numbers and other variants compare strictly
+    assert!(!Value::Number(f64::NAN).approx_eq(&Value::Number(f64::NAN), DEFAULT_EPSILON));
+    assert!(!Value::Number(f64::INFINITY).approx_eq(&Value::Number(f64::MAX), DEFAULT_EPSILON));
+    assert!(Value::String("a".to_string()).approx_eq(&Value::String("a".to_string()), DEFAULT_EPSILON));
+    assert!(!Value::Bool(true).approx_eq(&Value::Number(1.0), DEFAULT_EPSILON));
+
+    // The interpreter's equality operators use the default tolerance
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(evaluate("0.1 + 0.2 == 0.3"), Value::Bool(true));
+    assert_eq!(evaluate("0.1 + 0.2 != 0.3"), Value::Bool(false));
+    assert_eq!(evaluate("0.1 + 0.2 == 0.31"), Value::Bool(false));
+
+    // So do the ordering operators, numbers equal within it being neither smaller nor larger
+    assert_eq!(evaluate("0.1 + 0.2 <= 0.3"), Value::Bool(true));
+    assert_eq!(evaluate("0.3 >= 0.1 + 0.2"), Value::Bool(true));
+    assert_eq!(evaluate("0.1 + 0.2 > 0.3"), Value::Bool(false));
+    assert_eq!(evaluate("0.3 < 0.1 + 0.2"), Value::Bool(false));
+    assert_eq!(evaluate("0.3 < 0.30001"), Value::Bool(true));
+    assert_eq!(evaluate("0.30001 <= 0.3"), Value::Bool(false));
+    // NaN is never ordered, even loosely
+    assert_eq!(evaluate("sqrt(-1) <= sqrt(-1)"), Value::Bool(false));
+}
+
+#[test]
+fn test_user_defined_functions() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError, MAX_CALL_DEPTH},
+        parser::{parse_expr, parse_statements, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    // Defining a function has no value of its own
+    assert_eq!(evaluate("fn square(x) := x * x"), Ok(Value::None));
+    assert_eq!(evaluate("square(4)"), Ok(Value::Number(16.0)));
+    assert_eq!(evaluate("square(2 + 1) + 1"), Ok(Value::Number(10.0)));
+
+    // Parameters are local to the call and shadow global variables
+    assert_eq!(evaluate("var x := 100"), Ok(Value::Number(100.0)));
+    assert_eq!(evaluate("square(3)"), Ok(Value::Number(9.0)));
+    assert_eq!(evaluate("x"), Ok(Value::Number(100.0)));
+
+    // Bodies can read globals and call other functions
+    assert_eq!(evaluate("fn offset(y) := square(y) + x"), Ok(Value::None));
+    assert_eq!(evaluate("offset(2)"), Ok(Value::Number(104.0)));
+
+    assert_eq!(
+        evaluate("square(1, 2)"),
+        Err(RuntimeError::WrongArity {
+            func: "square".to_string(),
+            expected: 1,
+            got: 2,
+        })
+    );
+
+    // Unbounded recursion stops at the depth limit instead of overflowing the stack
+    assert_eq!(evaluate("fn forever(n) := forever(n + 1)"), Ok(Value::None));
+    assert_eq!(
+        evaluate("forever(0)"),
+        Err(RuntimeError::RecursionLimitExceeded {
+            func: "forever".to_string(),
+            limit: MAX_CALL_DEPTH,
+        })
+    );
+    // The interpreter is still usable afterwards
+    assert_eq!(evaluate("square(5)"), Ok(Value::Number(25.0)));
+
+    // Definitions work as statements in a script
+    let statements = parse_statements(&tokenize("fn double(n) := n * 2\ndouble(21)")).unwrap();
+    let mut interpreter = Interpreter::new();
+    assert_eq!(statements[0].accept(&mut interpreter), Ok(Value::None));
+    assert_eq!(statements[1].accept(&mut interpreter), Ok(Value::Number(42.0)));
+
+    assert!(parse_expr(&tokenize("fn (x) := x")).is_err());
+    assert!(parse_expr(&tokenize("fn f(1) := 1")).is_err());
+    assert!(parse_expr(&tokenize("fn f(x) x")).is_err());
+}
+
+#[test]
+fn test_function_scopes() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    // A parameter named like a global does not clobber it
+    evaluate("var x := 7").unwrap();
+    evaluate("fn triple(x) := x * 3").unwrap();
+    assert_eq!(evaluate("triple(2)"), Ok(Value::Number(6.0)));
+    assert_eq!(evaluate("x"), Ok(Value::Number(7.0)));
+
+    // Variables declared inside a body are local to the call
+    evaluate("fn shadow(n) := var x := n + 1").unwrap();
+    assert_eq!(evaluate("shadow(41)"), Ok(Value::Number(42.0)));
+    assert_eq!(evaluate("x"), Ok(Value::Number(7.0)));
+
+    // Parameters do not leak out of the call, nor into the functions it calls
+    evaluate("fn peek() := secret").unwrap();
+    evaluate("fn outer(secret) := peek()").unwrap();
+    assert_eq!(evaluate("outer(1)"), Err(RuntimeError::UndefinedVariable("secret".to_string())));
+    assert_eq!(evaluate("secret"), Err(RuntimeError::UndefinedVariable("secret".to_string())));
+
+    // Global assignments still work at the top scope
+    evaluate("var y := 2").unwrap();
+    assert_eq!(interpreter.variables().get("y"), Some(&Value::Integer(2)));
+    assert_eq!(interpreter.variables().get("n"), None);
+}
+
+#[test]
+fn test_tokenize_digit_separators() {
+    use crate::tokenizer::{tokenize, Token};
+
+    assert_eq!(tokenize("1_000"), vec![Token::Integer(1000)]);
+    assert_eq!(tokenize("1_000_000"), vec![Token::Integer(1_000_000)]);
+    assert_eq!(tokenize("2.718_5"), vec![Token::Number(2.7185)]);
+    assert_eq!(tokenize("1e1_0"), vec![Token::Number(1e10)]);
+    assert_eq!(
+        tokenize("x := -2_500"),
+        vec![
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Integer(-2500)
+        ]
+    );
+
+    // Separators must sit between two digits
+    for malformed in ["1__0", "_1", "1_", "1_.5", "1._5", "1_e5", "1e_5"] {
+        assert_eq!(
+            tokenize(malformed),
+            vec![Token::Unknown(malformed.to_string())],
+            "{} should be rejected",
+            malformed
+        );
+    }
+}
+
+#[test]
+fn test_tokenize_hex_and_binary_literals() {
+    use crate::tokenizer::{tokenize, Token};
+
+    assert_eq!(tokenize("0xFF"), vec![Token::Integer(255)]);
+    assert_eq!(tokenize("0xff"), vec![Token::Integer(255)]);
+    assert_eq!(tokenize("0b1010"), vec![Token::Integer(10)]);
+    assert_eq!(tokenize("0xFF_FF"), vec![Token::Integer(65535)]);
+    assert_eq!(tokenize("0B1111_0000"), vec![Token::Integer(240)]);
+    assert_eq!(
+        tokenize("0x10 + -0b11"),
+        vec![
+            Token::Integer(16),
+            Token::Operator("+".to_string()),
+            Token::Integer(-3)
+        ]
+    );
+
+    // Malformed literals are rejected as a whole
+    for malformed in ["0x", "0xG", "0b102", "0b", "0x_F", "0xF_", "0x1__0"] {
+        assert_eq!(
+            tokenize(malformed),
+            vec![Token::Unknown(malformed.to_string())],
+            "{} should be rejected",
+            malformed
+        );
+    }
+}
+
+#[test]
+fn test_unterminated_string_literal() {
+    use crate::{
+        parser::{parse_expr, parse_statements, ScriptError},
+        tokenizer::{tokenize, LexError, Token},
+    };
+
+    assert_eq!(
+        tokenize("x := \"hello"),
+        vec![
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Error(LexError::UnterminatedString(5))
+        ]
+    );
+    // Closed strings are unaffected
+    assert_eq!(tokenize("\"hello\""), vec![Token::StringLiteral("hello".to_string())]);
+
+    // The parser reports the error instead of the rest of the input
+    match parse_expr(&tokenize("x := \"hello")) {
+        Err(ScriptError::Lex(error)) => {
+            assert_eq!(error, LexError::UnterminatedString(5));
+            assert!(error.to_string().contains("position 5"), "{}", error);
+        }
+        Err(other) => panic!("Expected a lexical error, got {}", other),
+        Ok(_) => panic!("Expected a lexical error"),
+    }
+    assert!(matches!(
+        parse_statements(&tokenize("a := 1\nb := \"oops")),
+        Err(ScriptError::Lex(LexError::UnterminatedString(12)))
+    ));
+}
+
+#[test]
+fn test_tokenize_sections() {
+    use crate::tokenizer::{tokenize, Token};
+
+    assert_eq!(
+        tokenize("begin main\nx := 1\nend main"),
+        vec![
+            Token::BeginSection("main".to_string()),
+            Token::Newline,
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Integer(1),
+            Token::Newline,
+            Token::EndSection("main".to_string())
+        ]
+    );
+
+    // A unary minus is allowed right after a section start
+    assert_eq!(
+        tokenize("begin calc -2 end calc"),
+        vec![
+            Token::BeginSection("calc".to_string()),
+            Token::Integer(-2),
+            Token::EndSection("calc".to_string())
+        ]
+    );
+
+    // Without a name on the same line the keywords stay identifiers
+    assert_eq!(
+        tokenize("end := 3"),
+        vec![
+            Token::Identifier("end".to_string()),
+            Token::Assign,
+            Token::Integer(3)
+        ]
+    );
+    assert_eq!(
+        tokenize("begin\nmain"),
+        vec![
+            Token::Identifier("begin".to_string()),
+            Token::Newline,
+            Token::Identifier("main".to_string())
+        ]
+    );
+    assert_eq!(tokenize("beginning"), vec![Token::Identifier("beginning".to_string())]);
+}
+
+#[test]
+fn test_integer_arithmetic() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Value},
+        tokenizer::{tokenize, Token},
+    };
+
+    // Literals without a decimal point are integers
+    assert_eq!(tokenize("5"), vec![Token::Integer(5)]);
+    assert_eq!(tokenize("5.0"), vec![Token::Number(5.0)]);
+    assert_eq!(tokenize("5e0"), vec![Token::Number(5.0)]);
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    // int op int = int, with truncating division
+    assert!(matches!(evaluate("5 / 2"), Ok(Value::Integer(2))));
+    assert!(matches!(evaluate("-7 / 2"), Ok(Value::Integer(-3))));
+    assert!(matches!(evaluate("7 % 3"), Ok(Value::Integer(1))));
+    assert!(matches!(evaluate("2 ^ 10"), Ok(Value::Integer(1024))));
+    assert!(matches!(evaluate("3 * 4 - 1"), Ok(Value::Integer(11))));
+
+    // int op float = float
+    assert!(matches!(evaluate("5.0 / 2"), Ok(Value::Number(x)) if x == 2.5));
+    assert!(matches!(evaluate("5 / 2.0"), Ok(Value::Number(x)) if x == 2.5));
+    assert!(matches!(evaluate("2 ^ -1"), Ok(Value::Number(x)) if x == 0.5));
+    assert_eq!(evaluate("2 == 2.0"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("3 < 3.5"), Ok(Value::Bool(true)));
+
+    // Integer types survive assignment
+    assert!(matches!(evaluate("var n := 9"), Ok(Value::Integer(9))));
+    assert!(matches!(evaluate("n / 2"), Ok(Value::Integer(4))));
+
+    // Integer division by zero and overflow are errors
+    assert_eq!(evaluate("1 / 0"), Err(RuntimeError::DivisionByZero));
+    assert_eq!(
+        evaluate("9223372036854775807 + 1"),
+        Err(RuntimeError::IntegerOverflow { op: "+".to_string() })
+    );
+    // Float division by zero is an error too
+    assert_eq!(evaluate("1.0 / 0"), Err(RuntimeError::DivisionByZero));
+
+    // Integers and floats with the same value compare equal
+    assert_eq!(Value::Integer(2), Value::Number(2.0));
+    assert_ne!(Value::Integer(2), Value::Number(2.5));
+    assert_eq!(Value::Integer(2).to_string(), "2");
+}
+
+#[test]
+fn test_mod_function() {
+    use crate::{
+        interpreter::Interpreter,
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    assert_eq!(MathModule::modulo(10.0, 3.0), 1.0);
+    // The sign follows the dividend
+    assert_eq!(MathModule::modulo(-10.0, 3.0), -1.0);
+    assert_eq!(MathModule::modulo(10.0, -3.0), 1.0);
+    assert_eq!(MathModule::modulo(5.5, 2.0), 1.5);
+    assert!(MathModule::modulo(5.0, 0.0).is_nan());
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(evaluate("mod(10, 3)"), Value::Number(1.0));
+    assert_eq!(evaluate("mod(-10, 3)"), Value::Number(-1.0));
+    match evaluate("mod(5, 0)") {
+        Value::Number(val) => assert!(val.is_nan()),
+        other => panic!("mod(5, 0) should be NaN, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sign() {
+    use crate::{
+        interpreter::Interpreter,
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    assert_eq!(MathModule::sign(-3.0), -1.0);
+    assert_eq!(MathModule::sign(2.5), 1.0);
+    // Zero has no sign, whichever zero it is
+    assert_eq!(MathModule::sign(0.0), 0.0);
+    assert_eq!(MathModule::sign(-0.0), 0.0);
+    assert!(MathModule::sign(f64::NAN).is_nan());
+    assert_eq!(MathModule::sign(f64::NEG_INFINITY), -1.0);
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(evaluate("sign(-3)"), Value::Number(-1.0));
+    assert_eq!(evaluate("sign(0)"), Value::Number(0.0));
+    assert_eq!(evaluate("sign(2.5)"), Value::Number(1.0));
+}
+
+#[test]
+fn test_degree_trigonometric_functions() {
+    use crate::{
+        interpreter::Interpreter,
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    assert_eq!(MathModule::sind(90.0), 1.0);
+    assert_eq!(MathModule::cosd(0.0), 1.0);
+    assert!((MathModule::sind(30.0) - 0.5).abs() < 1e-12);
+    assert!((MathModule::cosd(60.0) - 0.5).abs() < 1e-12);
+    assert!((MathModule::tand(45.0) - 1.0).abs() < 1e-12);
+    // Undefined tangents are NaN, as with tan
+    assert!(MathModule::tand(90.0).is_nan());
+    assert!(MathModule::tand(-90.0).is_nan());
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(evaluate("sind(90)"), Value::Number(1.0));
+    match evaluate("tand(90)") {
+        Value::Number(val) => assert!(val.is_nan()),
+        other => panic!("tand(90) should be NaN, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_hypot() {
+    use crate::{
+        interpreter::Interpreter,
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    assert_eq!(MathModule::hypot(3.0, 4.0), 5.0);
+    assert_eq!(MathModule::hypot(-3.0, 4.0), 5.0);
+
+    // A naive sqrt(x*x + y*y) overflows here
+    let naive = MathModule::sqrt(1e200 * 1e200 + 1e200 * 1e200);
+    assert!(naive.is_infinite());
+    let result = MathModule::hypot(1e200, 1e200);
+    assert!(result.is_finite());
+    assert!((result / (1e200 * std::f64::consts::SQRT_2) - 1.0).abs() < 1e-12);
+
+    let mut interpreter = Interpreter::new();
+    let mut evaluate = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(evaluate("hypot(3, 4)"), Value::Number(5.0));
+}
+
+#[test]
+fn test_compile_to_bytecode() {
+    use crate::{
+        compiler::{compile, CompileError, Op},
+        parser::parse_statements,
+        tokenizer::tokenize,
+    };
+
+    let nodes = parse_statements(&tokenize("x := 3 + 4")).unwrap();
+    assert_eq!(
+        compile(&nodes).unwrap(),
+        vec![
+            Op::PushInt(3),
+            Op::PushInt(4),
+            Op::Add,
+            Op::StoreVar("x".to_string()),
+        ]
+    );
+
+    // Operands come in evaluation order, respecting precedence
+    let nodes = parse_statements(&tokenize("y := (x - 1) * max(x, 2.5) / 2\ny")).unwrap();
+    assert_eq!(
+        compile(&nodes).unwrap(),
+        vec![
+            Op::LoadVar("x".to_string()),
+            Op::PushInt(1),
+            Op::Sub,
+            Op::LoadVar("x".to_string()),
+            Op::PushNum(2.5),
+            Op::Call("max".to_string(), 2),
+            Op::Mul,
+            Op::PushInt(2),
+            Op::Div,
+            Op::StoreVar("y".to_string()),
+            Op::LoadVar("y".to_string()),
+        ]
+    );
+
+    // Constructs without an instruction are reported
+    let nodes = parse_statements(&tokenize("x := \"text\"")).unwrap();
+    assert_eq!(
+        compile(&nodes),
+        Err(CompileError::Unsupported("a string literal".to_string()))
+    );
+    let nodes = parse_statements(&tokenize("2 ^ 3")).unwrap();
+    assert_eq!(
+        compile(&nodes),
+        Err(CompileError::Unsupported("operator '^'".to_string()))
+    );
+}
+
+#[test]
+fn test_vm_matches_interpreter() {
+    use crate::{
+        compiler::{compile, Op, VM},
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, parse_statements, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut vm = VM::new();
+    let ops = compile(&parse_statements(&tokenize("var x := 2 * (3 + 4)")).unwrap()).unwrap();
+    assert_eq!(vm.run(&ops), Ok(Value::Integer(14)));
+    assert_eq!(vm.variables().get("x"), Some(&Value::Integer(14)));
+
+    // Both execution paths agree on a batch of expressions sharing variables
+    let sources = [
+        "var x := 2 * (3 + 4)",
+        "x / 4",
+        "x / 4.0",
+        "var y := x - 20",
+        "sqrt(x + 2) * y",
+        "max(x, 20) + min(1.5, y)",
+        "clamp(y, 0, 10)",
+        "PI * 2",
+        "hypot(3, 4) / 0.5",
+    ];
+    let mut vm = VM::new();
+    let mut interpreter = Interpreter::new();
+    for source in sources {
+        let expected = parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter).unwrap();
+        let ops = compile(&parse_statements(&tokenize(source)).unwrap()).unwrap();
+        assert_eq!(vm.run(&ops).unwrap(), expected, "{}", source);
+    }
+
+    // Errors match too
+    let ops = compile(&parse_statements(&tokenize("x / 0")).unwrap()).unwrap();
+    assert_eq!(vm.run(&ops), Err(RuntimeError::DivisionByZero));
+    let ops = compile(&parse_statements(&tokenize("missing + 1")).unwrap()).unwrap();
+    assert_eq!(vm.run(&ops), Err(RuntimeError::UndefinedVariable("missing".to_string())));
+    let ops = compile(&parse_statements(&tokenize("nope(1)")).unwrap()).unwrap();
+    assert_eq!(vm.run(&ops), Err(RuntimeError::UnknownFunction("nope".to_string())));
+    assert_eq!(
+        vm.run(&[Op::PushNum(1.0), Op::Add]),
+        Err(RuntimeError::StackUnderflow("+".to_string()))
+    );
+}
+
+#[test]
+fn test_script_reports_unknown_input() {
+    use crate::{
+        parser::{parse_script, ScriptError},
+        tokenizer::{tokenize_with_positions, LexError, Token},
+    };
+    use std::fs;
+
+    assert_eq!(
+        tokenize_with_positions("x := @ 1"),
+        vec![
+            (Token::Identifier("x".to_string()), 0),
+            (Token::Assign, 2),
+            (Token::Unknown("@".to_string()), 5),
+            (Token::Integer(1), 7),
+        ]
+    );
+
+    let path = std::env::temp_dir().join(format!("oak_lex_test_{}.oak", std::process::id()));
+    fs::write(&path, "a := 1\nb := a @ 2\n").unwrap();
+    let result = parse_script(path.to_string_lossy().to_string());
+    fs::remove_file(&path).unwrap();
+
+    match result {
+        Err(ScriptError::Lex(error)) => {
+            assert_eq!(
+                error,
+                LexError::UnexpectedInput {
+                    text: "@".to_string(),
+                    position: 14
+                }
+            );
+            let message = ScriptError::Lex(error).to_string();
+            assert!(message.contains("'@'"), "{}", message);
+        }
+        Err(other) => panic!("Expected a lexical error, got {}", other),
+        Ok(_) => panic!("A script containing '@' should not parse"),
+    }
+}
+
+#[test]
+fn test_repl_exit_codes_and_help() {
+    use crate::repl::{parse_exit_command, Repl};
+
+    assert_eq!(parse_exit_command("exit"), Some(Ok(0)));
+    assert_eq!(parse_exit_command("quit"), Some(Ok(0)));
+    assert_eq!(parse_exit_command("exit 2"), Some(Ok(2)));
+    assert_eq!(parse_exit_command("  quit   -1 "), Some(Ok(-1)));
+    assert_eq!(
+        parse_exit_command("exit two"),
+        Some(Err("Invalid exit code 'two'".to_string()))
+    );
+    assert_eq!(parse_exit_command("exit := 3"), None);
+    assert_eq!(parse_exit_command("exits"), None);
+    assert_eq!(parse_exit_command("x + 1"), None);
+
+    // An invalid code is reported without leaving the loop
+    let mut output = Vec::new();
+    let code = Repl::new()
+        .run("exit nope\n1 + 1\nexit 3\n4\n".as_bytes(), &mut output)
+        .unwrap();
+    assert_eq!(code, 3);
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Error: Invalid exit code 'nope'"), "{}", output);
+    assert!(output.contains("2\n"), "{}", output);
+    assert!(!output.contains("4\n"), "{}", output);
+
+    // End of input exits with 0
+    let mut output = Vec::new();
+    assert_eq!(Repl::new().run("1\n".as_bytes(), &mut output).unwrap(), 0);
+
+    let mut output = Vec::new();
+    Repl::new().run(":help\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    for command in [":vars", ":clear", ":history", ":funcs", ":load", ":save", ":precision", ":mode", ":help", "exit [code]"] {
+        assert!(output.contains(command), "{} missing from help: {}", command, output);
+    }
+}
+
+#[test]
+fn test_repl_multiline_input() {
+    use crate::repl::{is_complete, Repl};
+
+    assert!(is_complete("3 + 4"));
+    assert!(is_complete("max(1, 2)"));
+    assert!(is_complete("x := \"a (\""));
+    assert!(is_complete("x := 1 # trailing comment"));
+    // Extra closing parens are left for the parser to report
+    assert!(is_complete("1)"));
+    assert!(!is_complete("(3 +"));
+    assert!(!is_complete("3 *"));
+    assert!(!is_complete("x :="));
+    assert!(!is_complete("max(1,"));
+    assert!(!is_complete("x := \"unterminated"));
+    assert!(!is_complete("1 + # comment"));
+
+    let mut repl = Repl::new();
+    let mut output = Vec::new();
+    repl.run("(3 +\n4)\n:history\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("... 7\n"), "{}", output);
+    assert!(output.contains("   1  (3 + 4)\n"), "{}", output);
+    assert_eq!(repl.history(), ["(3 + 4)", ":history"]);
+
+    // Input left incomplete at the end is still reported
+    let mut output = Vec::new();
+    Repl::new().run("(1 +\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Error:"), "{}", output);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_calc_architecture_json() {
+    use crate::math::{calc_architecture, calc_architecture_json, ArchitecturalResult};
+
+    let params = [3.125, 1.0, 6.0, 8.0, 10.0, 1.0, 5.0, 2.0];
+    let json = calc_architecture_json("stability", &params);
+    assert!(json.starts_with("{\"stability\":{"), "{}", json);
+    assert!(json.contains("\"is_stable\":true"), "{}", json);
+
+    let Ok(ArchitecturalResult::Stability(expected)) = calc_architecture("stability", &params) else {
+        panic!("Expected a stability result");
+    };
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let fields = &value["stability"];
+    assert_eq!(fields["resisting_moment"].as_f64(), Some(expected.resisting_moment));
+    assert_eq!(fields["overturning_moment"].as_f64(), Some(expected.overturning_moment));
+    assert_eq!(fields["stability_ratio"].as_f64(), Some(expected.stability_ratio));
+    assert_eq!(fields["safety_margin"].as_f64(), Some(expected.safety_margin));
+    assert_eq!(fields["safety_factor"].as_f64(), Some(2.0));
+
+    let json = calc_architecture_json("wind_stiffness", &[10.0, 40.0]);
+    assert!(json.contains("\"wind_stiffness\":{"), "{}", json);
+    assert!(json.contains("\"is_compliant\":true"), "{}", json);
+    assert_eq!(
+        calc_architecture_json("minimum_dead_load", &[]),
+        format!("{{\"error\":\"{}\"}}", calc_architecture("minimum_dead_load", &[]).unwrap_err())
+    );
+
+    // Failures come back as an error object
+    let value: serde_json::Value = serde_json::from_str(&calc_architecture_json("unknown", &[])).unwrap();
+    assert_eq!(value["error"], "Unknown architectural calculation 'unknown'");
+}
+
+#[test]
+fn test_calc_architecture_batch() {
+    use crate::math::{calc_architecture_batch, calc_architecture_command};
+
+    let rows = vec![
+        vec![10.0, 40.0],
+        vec![5.0, 50.0],
+        vec![-1.0, 40.0],
+        vec![10.0],
+        vec![20.0, 20.0],
+    ];
+    let results = calc_architecture_batch("wind_stiffness", rows.clone());
+    assert_eq!(results.len(), rows.len());
+    for (result, row) in results.iter().zip(&rows) {
+        assert_eq!(result, &calc_architecture_command("wind_stiffness", row));
+    }
+
+    assert!(results[0].contains("The building is compliant"));
+    assert!(results[1].contains("The building is not compliant"));
+    // Invalid rows are reported without aborting the rows after them
+    assert!(results[2].starts_with("Error"));
+    assert!(results[3].starts_with("Error"));
+    assert!(results[4].contains("The building is compliant"));
+
+    assert!(calc_architecture_batch("stability", Vec::new()).is_empty());
+    assert!(calc_architecture_batch("unknown", vec![vec![1.0]])[0].contains("Error"));
+}
+
+#[test]
+fn test_wind_pressure() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule};
+
+    // q = 0.5 * 1.25 * 30² * 1.0
+    let pressure = MathModule::calculate_wind_pressure(30.0, 1.25, 1.0).unwrap();
+    assert!((pressure - 562.5).abs() < 1e-9);
+    let pressure = MathModule::calculate_wind_pressure(40.0, 1.25, 0.8).unwrap();
+    assert!((pressure - 800.0).abs() < 1e-9);
+
+    match calc_architecture("wind_pressure", &[30.0, 1.25, 1.0]) {
+        Ok(ArchitecturalResult::WindPressure(pressure)) => assert!((pressure - 562.5).abs() < 1e-9),
+        other => panic!("Expected a wind pressure result, got {:?}", other),
+    }
+    assert_eq!(
+        calc_architecture_command("wind_pressure", &[30.0, 1.25, 1.0]),
+        "Wind pressure: 562.50 N/m² (0.562 kN/m²)"
+    );
+
+    assert_eq!(
+        MathModule::calculate_wind_pressure(-30.0, 1.25, 1.0).unwrap_err(),
+        "Wind speed must be positive and finite"
+    );
+    assert!(MathModule::calculate_wind_pressure(30.0, 0.0, 1.0).is_err());
+    assert!(MathModule::calculate_wind_pressure(f64::INFINITY, 1.25, 1.0).is_err());
+    assert!(calc_architecture("wind_pressure", &[30.0, 1.25]).is_err());
+}
+
+#[test]
+fn test_center_to_corner_distance_with_offset() {
+    use crate::math::MathModule;
+
+    // Without offset the arm reaches the corner from the geometric center
+    let symmetric = MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, 0.0, 0.0).unwrap();
+    assert!((symmetric - 5.0).abs() < 1e-12);
+
+    // An offset center of gravity lengthens the arm, whatever its direction
+    let offset = MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, 1.0, -1.0).unwrap();
+    assert!((offset - 41.0f64.sqrt()).abs() < 1e-12);
+    assert!(offset > symmetric);
+    let mirrored = MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, -1.0, 1.0).unwrap();
+    assert!((offset - mirrored).abs() < 1e-12);
+
+    // On the edge of the footprint is still inside it
+    assert!(MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, 3.0, 4.0).is_ok());
+    assert_eq!(
+        MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, 3.5, 0.0).unwrap_err(),
+        "Center of gravity offset places it outside the building footprint"
+    );
+    assert!(MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, 0.0, -4.5).is_err());
+    assert!(MathModule::calculate_center_to_corner_distance_with_offset(6.0, 8.0, f64::NAN, 0.0).is_err());
+}
+
+#[test]
+fn test_format_architectural_result() {
+    use crate::math::{calc_architecture, calc_architecture_command, format_architectural_result};
+
+    let stability = [3.125, 1.0, 6.0, 8.0, 10.0, 1.0, 5.0, 2.0];
+    let result = calc_architecture("stability", &stability).unwrap();
+    assert_eq!(
+        format_architectural_result(&result),
+        calc_architecture_command("stability", &stability)
+    );
+    assert!(format_architectural_result(&result).ends_with("The building is stable"));
+
+    let result = calc_architecture("wind_stiffness", &[10.0, 40.0]).unwrap();
+    assert_eq!(
+        format_architectural_result(&result),
+        "Wind stiffness compliance\n\
+         Slenderness ratio: 0.250\n\
+         Minimum ratio: 0.200\n\
+         The building is compliant"
+    );
+    assert_eq!(
+        format_architectural_result(&result),
+        calc_architecture_command("wind_stiffness", &[10.0, 40.0])
+    );
+}
+
+#[test]
+fn test_building_floor_limit() {
+    use crate::math::{calc_architecture, MathModule, MAX_FLOORS};
+
+    let stability = |num_floors| MathModule::verify_building_stability(5.0, 1.0, 20.0, 15.0, 30.0, num_floors, 15.0);
+    assert!(stability(MAX_FLOORS).is_ok());
+    assert!(stability(200).is_ok());
+    assert_eq!(stability(201).unwrap_err(), "Number of floors exceeds realistic maximum");
+    assert!(stability(u32::MAX).is_err());
+
+    assert!(MathModule::calculate_minimum_dead_load(1.0, 20.0, 15.0, 30.0, 200, 15.0, 3.0).is_ok());
+    assert!(MathModule::calculate_minimum_dead_load(1.0, 20.0, 15.0, 30.0, 201, 15.0, 3.0).is_err());
+
+    // Floor counts that do not fit in a u32 are still reported by the conversion guard
+    let params = [5.0, 1.0, 20.0, 15.0, 30.0, 1e10, 15.0];
+    assert_eq!(calc_architecture("stability", &params).unwrap_err(), "Number of floors is out of range");
+}
+
+#[test]
+fn test_nested_assignment() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let value = parse_expr(&tokenize("var y := (var x := 5) + 1")).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(value, Value::Integer(6));
+    assert_eq!(interpreter.variables().get("x"), Some(&Value::Integer(5)));
+    assert_eq!(interpreter.variables().get("y"), Some(&Value::Integer(6)));
+
+    // Assignments chain from right to left
+    parse_expr(&tokenize("var a := var b := 2 * 3")).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(interpreter.variables().get("a"), Some(&Value::Integer(6)));
+    assert_eq!(interpreter.variables().get("b"), Some(&Value::Integer(6)));
+
+    // The inner assignment happens before the rest of the expression is evaluated
+    let value = parse_expr(&tokenize("(var z := 4) * z")).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(value, Value::Integer(16));
+}
+
+#[test]
+fn test_angle_mode() {
+    use crate::{
+        interpreter::{AngleMode, Interpreter},
+        parser::{parse_expr, Value},
+        repl::Repl,
+        tokenizer::tokenize,
+    };
+
+    let eval = |interpreter: &mut Interpreter, source: &str| {
+        match parse_expr(&tokenize(source)).unwrap().accept(interpreter).unwrap() {
+            Value::Number(value) => value,
+            other => panic!("Expected a number, got {:?}", other),
+        }
+    };
+
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.angle_mode(), AngleMode::Radians);
+    assert!((eval(&mut interpreter, "sin(90)") - 90f64.sin()).abs() < 1e-12);
+
+    interpreter.set_angle_mode(AngleMode::Degrees);
+    assert!((eval(&mut interpreter, "sin(90)") - 1.0).abs() < 1e-12);
+    assert!(eval(&mut interpreter, "cos(90)").abs() < 1e-12);
+    assert!((eval(&mut interpreter, "tan(45)") - 1.0).abs() < 1e-12);
+    // Inverse functions return degrees
+    assert!((eval(&mut interpreter, "asin(1)") - 90.0).abs() < 1e-12);
+    assert!((eval(&mut interpreter, "atan2(1, 1)") - 45.0).abs() < 1e-12);
+    // Other functions are unaffected
+    assert!((eval(&mut interpreter, "sqrt(16)") - 4.0).abs() < 1e-12);
+
+    interpreter.set_angle_mode(AngleMode::Radians);
+    assert!((eval(&mut interpreter, "sin(90)") - 90f64.sin()).abs() < 1e-12);
+
+    // Short names, as taken by `:mode` and OAK_ANGLE_MODE
+    assert_eq!("deg".parse::<AngleMode>(), Ok(AngleMode::Degrees));
+    assert_eq!("rad".parse::<AngleMode>(), Ok(AngleMode::Radians));
+    assert!("degrees".parse::<AngleMode>().is_err());
+
+    let mut output = Vec::new();
+    Repl::new()
+        .run(":mode deg\nsin(90)\n:mode\n:mode grad\n:mode rad\n".as_bytes(), &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().map(|line| line.trim_start_matches("> ")).collect();
+    assert_eq!(lines[0], "Angle mode: degrees");
+    assert_eq!(lines[1], "1");
+    assert_eq!(lines[2], "Angle mode: degrees");
+    assert_eq!(lines[3], "Unknown angle mode 'grad', expected deg or rad");
+    assert_eq!(lines[4], "Angle mode: radians");
+}
+
+#[test]
+fn test_interpreter_variable_accessors() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Value},
+        runtime::run_source_with_interpreter,
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.get_variable("x"), None);
+
+    interpreter.set_variable("x", 4.0);
+    assert_eq!(interpreter.get_variable("x"), Some(4.0));
+    let value = parse_expr(&tokenize("x * 2 + 1")).unwrap().accept(&mut interpreter).unwrap();
+    assert_eq!(value, Value::Number(9.0));
+
+    // Seed the inputs of a script and read its outputs back
+    interpreter.set_variable("width", 2.5);
+    run_source_with_interpreter("var area := width * x\nvar count := 3", &mut interpreter).unwrap();
+    assert_eq!(interpreter.get_variable("area"), Some(10.0));
+    // Integers are read back as numbers
+    assert_eq!(interpreter.get_variable("count"), Some(3.0));
+
+    // Setting a variable replaces its previous value
+    interpreter.set_variable("x", -1.0);
+    assert_eq!(interpreter.get_variable("x"), Some(-1.0));
+}
+
+#[test]
+fn test_register_function() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{FunctionCall, Node, Number, Value},
+    };
+
+    fn double(x: f64) -> f64 {
+        x * 2.0
+    }
+    fn negate(x: f64) -> f64 {
+        -x
+    }
+    let call = |name: &str, args: Vec<f64>| {
+        let args: Vec<Box<dyn Node>> = args
+            .into_iter()
+            .map(|value| Box::new(Number { value }) as Box<dyn Node>)
+            .collect();
+        FunctionCall::parse(name.to_string(), args)
+    };
+
+    let mut interpreter = Interpreter::new();
+    interpreter.register_function("double", double);
+    assert_eq!(call("double", vec![21.0]).accept(&mut interpreter).unwrap(), Value::Number(42.0));
+
+    // Registered functions shadow the built-ins of the same name
+    interpreter.register_function("sqrt", negate);
+    assert_eq!(call("sqrt", vec![4.0]).accept(&mut interpreter).unwrap(), Value::Number(-4.0));
+    interpreter.register_function("max", double);
+    assert_eq!(call("max", vec![3.0]).accept(&mut interpreter).unwrap(), Value::Number(6.0));
+    assert!(matches!(
+        call("max", vec![1.0, 2.0]).accept(&mut interpreter),
+        Err(RuntimeError::WrongArity { expected: 1, got: 2, .. })
+    ));
+
+    // Other interpreters keep the built-ins
+    assert_eq!(call("sqrt", vec![4.0]).accept(&mut Interpreter::new()).unwrap(), Value::Number(2.0));
+}
+
+#[test]
+fn test_power_is_right_associative() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut Interpreter::new()).unwrap();
+
+    assert_eq!(eval("2 ^ 3 ^ 2"), Value::Integer(512));
+    assert_eq!(eval("2 ^ (3 ^ 2)"), Value::Integer(512));
+    assert_eq!(eval("(2 ^ 3) ^ 2"), Value::Integer(64));
+    assert_eq!(eval("2 ^ 2 ^ 3 ^ 0"), Value::Integer(4));
+
+    // Power binds tighter than the other operators
+    assert_eq!(eval("2 * 3 ^ 2"), Value::Integer(18));
+    assert_eq!(eval("2 ^ 3 * 2"), Value::Integer(16));
+    assert_eq!(eval("1 + 2 ^ 2 ^ 2 - 1"), Value::Integer(16));
+    // The other operators stay left-associative
+    assert_eq!(eval("2 - 3 - 4"), Value::Integer(-5));
+    assert_eq!(eval("64 / 4 / 2"), Value::Number(8.0));
+}
+
+#[test]
+fn test_unary_operators() {
+    use crate::{
+        compiler::{compile, Op, VM},
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Value},
+        tokenizer::{tokenize, Token},
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    assert_eq!(eval("-(3 + 4)").unwrap(), Value::Integer(-7));
+    eval("var x := 5").unwrap();
+    assert_eq!(eval("-x").unwrap(), Value::Integer(-5));
+    assert_eq!(eval("--5").unwrap(), Value::Integer(5));
+    assert_eq!(eval("- -x").unwrap(), Value::Integer(5));
+    assert_eq!(eval("+x").unwrap(), Value::Integer(5));
+    assert_eq!(eval("-(1.5)").unwrap(), Value::Number(-1.5));
+    assert_eq!(eval("3 - -x").unwrap(), Value::Integer(8));
+    // Prefix minus binds tighter than `*` but looser than `^`
+    assert_eq!(eval("-x * 2").unwrap(), Value::Integer(-10));
+    assert_eq!(eval("-x ^ 2").unwrap(), Value::Integer(-25));
+    assert_eq!(eval("-sqrt(16)").unwrap(), Value::Number(-4.0));
+    // A literal behaves the same: the power binds first
+    assert_eq!(eval("-5 ^ 2").unwrap(), Value::Integer(-25));
+    assert_eq!(eval("-2^2").unwrap(), Value::Integer(-4));
+    assert_eq!(eval("(-2) ^ 2").unwrap(), Value::Integer(4));
+    assert_eq!(eval("3 * -2 ^ 2").unwrap(), Value::Integer(-12));
+    assert_eq!(eval("2 ^ -1").unwrap(), Value::Number(0.5));
+    // The minus stays an operator only before a power
+    assert_eq!(tokenize("-2 ^ 2")[0], Token::Operator("-".to_string()));
+    assert_eq!(tokenize("-2 * 2")[0], Token::Integer(-2));
+
+    assert_eq!(
+        eval("-\"abc\""),
+        Err(RuntimeError::TypeMismatch { op: "-".to_string() })
+    );
+    assert!(parse_expr(&tokenize("-")).is_err());
+
+    // The bytecode negates with a dedicated instruction
+    let ops = compile(&[parse_expr(&tokenize("-(3 + 4)")).unwrap()]).unwrap();
+    assert_eq!(ops, vec![Op::PushInt(3), Op::PushInt(4), Op::Add, Op::Neg]);
+    assert_eq!(VM::new().run(&ops).unwrap(), Value::Integer(-7));
+    let ops = compile(&[parse_expr(&tokenize("+2.5")).unwrap()]).unwrap();
+    assert_eq!(VM::new().run(&ops).unwrap(), Value::Number(2.5));
+}
+
+#[test]
+fn test_eval_math_exp_evaluates_expression() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{EvalMathExp, Node, Value},
+    };
+
+    let mut interpreter = Interpreter::new();
+    let expr = EvalMathExp::parse(vec!["3", "+", "4"]);
+    assert_eq!(expr.accept(&mut interpreter).unwrap(), Value::Number(7.0));
+
+    // The expression sees the interpreter state
+    interpreter.set_variable("x", 2.0);
+    let expr = EvalMathExp::parse(vec!["x", "*", "sqrt", "(", "9", ")"]);
+    assert_eq!(expr.accept(&mut interpreter).unwrap(), Value::Number(6.0));
+
+    let expr = EvalMathExp::parse(vec!["3", "+"]);
+    match expr.accept(&mut interpreter) {
+        Err(RuntimeError::InvalidExpression { expr, .. }) => assert_eq!(expr, "3 +"),
+        other => panic!("Expected an invalid expression error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_comments_between_statements() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, parse_str, Value},
+        runtime::{run_source, run_source_with_interpreter},
+        tokenizer::tokenize,
+    };
+
+    let source = "# inputs\nvar x := 3 # inline note\nvar y := x + # inside an expression\n 4\n# done";
+    assert_eq!(parse_str(source).unwrap().len(), 5);
+
+    let mut interpreter = Interpreter::new();
+    run_source_with_interpreter(source, &mut interpreter).unwrap();
+    assert_eq!(interpreter.variables().get("x"), Some(&Value::Integer(3)));
+    assert_eq!(interpreter.variables().get("y"), Some(&Value::Integer(7)));
+
+    // A trailing comment does not hide the value of the last statement
+    assert_eq!(run_source("var x := 5\nx * 2 # double").unwrap(), Value::Number(10.0));
+    assert_eq!(
+        parse_expr(&tokenize("3 # note")).unwrap().accept(&mut interpreter).unwrap(),
+        Value::Integer(3)
+    );
+}
+
+#[test]
+fn test_comment_nodes_capture_text() {
+    use crate::{
+        interpreter::RuntimeError,
+        parser::{parse_str, Assign, BinOp, Comment, Discard, EvalMathExp, FunctionCall, FunctionDef,
+            If, Integer, Number, StringLiteral, UnaryOp, Value, Var, Visitor, While},
+    };
+
+    // Records the statements it visits, with the text of comments
+    #[derive(Default)]
+    struct Outline(Vec<String>);
+
+    impl Visitor for Outline {
+        fn visit_eval_math_exp(&mut self, _: &EvalMathExp) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_bin_op(&mut self, _: &BinOp) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_unary_op(&mut self, _: &UnaryOp) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_number(&mut self, _: &Number) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_integer(&mut self, _: &Integer) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_var(&mut self, _: &Var) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_assign(&mut self, node: &Assign) -> Result<Value, RuntimeError> {
+            self.0.push(format!("assign {}", node.name));
+            Ok(Value::None)
+        }
+        fn visit_string_literal(&mut self, _: &StringLiteral) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_function_call(&mut self, _: &FunctionCall) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_function_def(&mut self, _: &FunctionDef) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_if(&mut self, _: &If) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_while(&mut self, _: &While) -> Result<Value, RuntimeError> { Ok(Value::None) }
+        fn visit_comment(&mut self, node: &Comment) -> Result<Value, RuntimeError> {
+            self.0.push(format!("comment {}", node.value));
+            Ok(Value::None)
+        }
+        fn visit_discard(&mut self, node: &Discard) -> Result<Value, RuntimeError> { node.statement.accept(self) }
+    }
+
+    let statements = parse_str("# header\nx := 1 # first\ny := x +\n# skipped\n 2\n").unwrap();
+    let mut outline = Outline::default();
+    for statement in &statements {
+        statement.accept(&mut outline).unwrap();
+    }
+    assert_eq!(outline.0, ["comment header", "assign x", "comment first", "assign y"]);
+}
+
+#[test]
+fn test_load_combination() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule, LOAD_COMBINATIONS};
+
+    // 1.2 * 5 + 1.6 * 3
+    let total = MathModule::calculate_load_combination(5.0, 3.0, 2.0, 1.0, "1.2D + 1.6L").unwrap();
+    assert!((total - 10.8).abs() < 1e-9);
+    // 1.2 * 5 + 1.0 * 2
+    let total = MathModule::calculate_load_combination(5.0, 3.0, 2.0, 1.0, "1.2D + 1.0W").unwrap();
+    assert!((total - 8.0).abs() < 1e-9);
+    // Spaces and case do not matter
+    let total = MathModule::calculate_load_combination(5.0, 3.0, 2.0, 1.0, "0.9d+1.0w").unwrap();
+    assert!((total - 6.5).abs() < 1e-9);
+
+    assert_eq!(
+        MathModule::calculate_load_combination(5.0, 3.0, 2.0, 1.0, "2.0D + 3.0L").unwrap_err(),
+        "Unknown load combination '2.0D + 3.0L'"
+    );
+    assert_eq!(
+        MathModule::calculate_load_combination(5.0, -3.0, 2.0, 1.0, "1.4D").unwrap_err(),
+        "Live load must be non-negative and finite"
+    );
+
+    // Combinations are numbered from 1 in calc_architecture
+    assert_eq!(LOAD_COMBINATIONS[2].0, "1.2D + 1.6L");
+    match calc_architecture("load_combination", &[5.0, 3.0, 2.0, 1.0, 3.0]) {
+        Ok(ArchitecturalResult::LoadCombination(result)) => {
+            assert_eq!(result.combination, "1.2D + 1.6L");
+            assert!((result.factored_load - 10.8).abs() < 1e-9);
+        }
+        other => panic!("Expected a load combination result, got {:?}", other),
+    }
+    assert_eq!(
+        calc_architecture_command("load_combination", &[5.0, 3.0, 2.0, 1.0, 3.0]),
+        "Load combination 1.2D + 1.6L\nFactored load: 10.800"
+    );
+    assert!(calc_architecture("load_combination", &[5.0, 3.0, 2.0, 1.0, 0.0]).is_err());
+    assert!(calc_architecture("load_combination", &[5.0, 3.0, 2.0, 1.0, 9.0]).is_err());
+    assert!(calc_architecture("load_combination", &[5.0, 3.0, 2.0, 1.0, 1.5]).is_err());
+}
+
+#[test]
+fn test_building_stability_default_height() {
+    use crate::math::MathModule;
+
+    let defaulted = MathModule::verify_building_stability_default_height(5.0, 1.0, 20.0, 15.0, 30.0, 10).unwrap();
+    let explicit = MathModule::verify_building_stability(5.0, 1.0, 20.0, 15.0, 30.0, 10, 30.0 / 2.0).unwrap();
+    assert_eq!(defaulted.resisting_moment, explicit.resisting_moment);
+    assert_eq!(defaulted.overturning_moment, explicit.overturning_moment);
+    assert_eq!(defaulted.stability_ratio, explicit.stability_ratio);
+    assert_eq!(defaulted.is_stable, explicit.is_stable);
+
+    // A higher resultant gives a larger overturning moment
+    let higher = MathModule::verify_building_stability(5.0, 1.0, 20.0, 15.0, 30.0, 10, 25.0).unwrap();
+    assert!(higher.overturning_moment > defaulted.overturning_moment);
+
+    // Invalid dimensions are reported before the height is halved
+    assert_eq!(
+        MathModule::verify_building_stability_default_height(5.0, 1.0, 20.0, 15.0, -30.0, 10).unwrap_err(),
+        "Building height must be positive"
+    );
+    assert_eq!(
+        MathModule::verify_building_stability_default_height(5.0, 1.0, 20.0, 15.0, 30.0, 0).unwrap_err(),
+        "Number of floors must be at least 1"
+    );
+}
+
+#[test]
+fn test_identifiers_starting_with_underscore() {
+    use crate::tokenizer::{tokenize, Token};
+
+    assert_eq!(tokenize("_x"), vec![Token::Identifier("_x".to_string())]);
+    assert_eq!(tokenize("_"), vec![Token::Identifier("_".to_string())]);
+    assert_eq!(tokenize("__init_2"), vec![Token::Identifier("__init_2".to_string())]);
+    assert_eq!(
+        tokenize("_temp := _ + 1"),
+        vec![
+            Token::Identifier("_temp".to_string()),
+            Token::Assign,
+            Token::Identifier("_".to_string()),
+            Token::Operator("+".to_string()),
+            Token::Integer(1),
+        ]
+    );
+    assert_eq!(
+        tokenize("begin _setup"),
+        vec![Token::BeginSection("_setup".to_string())]
+    );
+    // `var` stays the only keyword
+    assert_eq!(tokenize("_var"), vec![Token::Identifier("_var".to_string())]);
+    // An underscore before a digit is still a malformed number
+    assert!(matches!(tokenize("_1")[0], Token::Unknown(_)));
+}
+
+#[test]
+fn test_keyword_tokens() {
+    use crate::{
+        parser::{parse_expr, ScriptError},
+        tokenizer::{tokenize, Token},
+    };
+
+    assert_eq!(tokenize("if"), vec![Token::If]);
+    assert_eq!(tokenize("else"), vec![Token::Else]);
+    assert_eq!(tokenize("while"), vec![Token::While]);
+    assert_eq!(tokenize("fn"), vec![Token::Fn]);
+    assert_eq!(tokenize("return"), vec![Token::Return]);
+    assert_eq!(tokenize("var"), vec![Token::Var]);
+
+    // Identifiers merely starting with a keyword stay identifiers
+    for name in ["iffy", "elsewhere", "whiles", "fns", "returned", "If", "_if"] {
+        assert_eq!(tokenize(name), vec![Token::Identifier(name.to_string())]);
+    }
+    assert_eq!(
+        tokenize("if x else y"),
+        vec![
+            Token::If,
+            Token::Identifier("x".to_string()),
+            Token::Else,
+            Token::Identifier("y".to_string()),
+        ]
+    );
+
+    // Function definitions are introduced by the `fn` keyword
+    assert!(parse_expr(&tokenize("fn double(x) := x * 2")).is_ok());
+    assert!(matches!(parse_expr(&tokenize("fn := 1")), Err(ScriptError::Parse(_))));
+}
+
+#[test]
+fn test_if_expression() {
+    use crate::{
+        compiler::{compile, CompileError},
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, ScriptError, Value},
+        repl::is_complete,
+        tokenizer::{tokenize, Token},
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    assert_eq!(eval("if 3 < 4 then 1 else 0").unwrap(), Value::Number(1.0));
+    assert_eq!(eval("if 3 > 4 then 1 else 0").unwrap(), Value::Number(0.0));
+    // The else branch extends as far as possible
+    assert_eq!(eval("if 3 > 4 then 1 else 2 + 3").unwrap(), Value::Integer(5));
+    assert_eq!(eval("(if 3 > 4 then 1 else 2) + 3").unwrap(), Value::Integer(5));
+    // Only the chosen branch is evaluated
+    eval("var x := 10").unwrap();
+    assert_eq!(eval("if x == 10 then x / 2 else undefined_thing").unwrap(), Value::Number(5.0));
+    assert_eq!(eval("if x < 0 then 0 - 1 else if x == 0 then 0 else 1").unwrap(), Value::Integer(1));
+    assert_eq!(eval("var y := if x > 5 then x else 5").unwrap(), Value::Integer(10));
+
+    assert_eq!(
+        eval("if 1 then 2 else 3"),
+        Err(RuntimeError::TypeMismatch { op: "if".to_string() })
+    );
+
+    assert_eq!(tokenize("then"), vec![Token::Then]);
+    assert!(matches!(parse_expr(&tokenize("if x then 1")), Err(ScriptError::Parse(_))));
+    assert!(matches!(parse_expr(&tokenize("if x 1 else 2")), Err(ScriptError::Parse(_))));
+    assert!(!is_complete("if x > 1 then"));
+    assert!(matches!(
+        compile(&[parse_expr(&tokenize("if 1 < 2 then 1 else 2")).unwrap()]),
+        Err(CompileError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_while_loop() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, ScriptError, Value},
+        runtime::run_source_with_interpreter,
+        tokenizer::tokenize,
+    };
+
+    // Sum 1..=10, the body increments the counter and adds it to the total
+    let mut interpreter = Interpreter::new();
+    run_source_with_interpreter("var i := 0\nvar total := 0\nwhile i < 10 do total := total + (i := i + 1)", &mut interpreter)
+        .unwrap();
+    assert_eq!(interpreter.variables().get("total"), Some(&Value::Integer(55)));
+    assert_eq!(interpreter.variables().get("i"), Some(&Value::Integer(10)));
+
+    // A false condition never runs the body
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+    assert_eq!(eval("while 1 > 2 do undefined_thing").unwrap(), Value::None);
+    assert_eq!(
+        eval("while 1 do 2"),
+        Err(RuntimeError::TypeMismatch { op: "while".to_string() })
+    );
+    assert!(matches!(parse_expr(&tokenize("while x 1")), Err(ScriptError::Parse(_))));
+}
+
+#[test]
+fn test_while_loop_iteration_limit() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError, DEFAULT_MAX_ITERATIONS},
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_iterations(1000);
+    parse_expr(&tokenize("var n := 0")).unwrap().accept(&mut interpreter).unwrap();
+    let runaway = parse_expr(&tokenize("while 1 == 1 do n := n + 1")).unwrap();
+    assert_eq!(
+        runaway.accept(&mut interpreter),
+        Err(RuntimeError::IterationLimitExceeded { limit: 1000 })
+    );
+    assert_eq!(interpreter.variables().get("n"), Some(&Value::Integer(1000)));
+
+    // Loops ending exactly at the limit are fine
+    let bounded = parse_expr(&tokenize("while n < 2000 do n := n + 1")).unwrap();
+    assert_eq!(bounded.accept(&mut interpreter), Ok(Value::None));
+
+    // The default cap stops an always-true loop too
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        parse_expr(&tokenize("while 1 < 2 do 0")).unwrap().accept(&mut interpreter),
+        Err(RuntimeError::IterationLimitExceeded { limit: DEFAULT_MAX_ITERATIONS })
+    );
+}
+
+#[test]
+fn test_variadic_aggregates() {
+    use crate::{
+        compiler::{compile, VM},
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    assert_eq!(eval("max(3, 7, 2)").unwrap(), Value::Integer(7));
+    assert_eq!(eval("min(3, 7, 2)").unwrap(), Value::Integer(2));
+    assert_eq!(eval("sum(1, 2, 3, 4)").unwrap(), Value::Integer(10));
+    assert_eq!(eval("max(5)").unwrap(), Value::Integer(5));
+    // Floats promote the result, as with the arithmetic operators
+    assert_eq!(eval("max(1, 2.5, 2)").unwrap(), Value::Number(2.5));
+    assert_eq!(eval("sum(0.5, 1, 1)").unwrap(), Value::Number(2.5));
+    assert_eq!(eval("min(3, -7)").unwrap(), Value::Number(-7.0));
+
+    assert_eq!(eval("max()"), Err(RuntimeError::MissingArguments("max".to_string())));
+    assert_eq!(eval("min()"), Err(RuntimeError::MissingArguments("min".to_string())));
+    assert_eq!(eval("sum()"), Err(RuntimeError::MissingArguments("sum".to_string())));
+    assert_eq!(eval("sum(1, \"a\")"), Err(RuntimeError::TypeMismatch { op: "sum".to_string() }));
+    assert_eq!(
+        eval("sum(9223372036854775807, 1)"),
+        Err(RuntimeError::IntegerOverflow { op: "+".to_string() })
+    );
+
+    // The VM follows the same rules
+    let ops = compile(&[parse_expr(&tokenize("sum(1, 2, 3, 4) + max(3, 7, 2)")).unwrap()]).unwrap();
+    assert_eq!(VM::new().run(&ops).unwrap(), Value::Integer(17));
+    let ops = compile(&[parse_expr(&tokenize("min()")).unwrap()]).unwrap();
+    assert_eq!(VM::new().run(&ops), Err(RuntimeError::MissingArguments("min".to_string())));
+}
+
+#[test]
+fn test_mean_and_stddev() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    assert_eq!(eval("mean(2, 4, 6)").unwrap(), Value::Number(4.0));
+    assert_eq!(eval("mean(1, 2)").unwrap(), Value::Number(1.5));
+    match eval("stddev(2, 4, 6)") {
+        Ok(Value::Number(value)) => assert!((value - 1.632993).abs() < 1e-6),
+        other => panic!("Expected a number, got {:?}", other),
+    }
+    assert_eq!(eval("stddev(5)").unwrap(), Value::Number(0.0));
+    assert_eq!(eval("mean()"), Err(RuntimeError::MissingArguments("mean".to_string())));
+    assert_eq!(eval("stddev()"), Err(RuntimeError::MissingArguments("stddev".to_string())));
+
+    assert!((MathModule::mean(&[1.0, 2.0, 3.0, 4.0]) - 2.5).abs() < 1e-12);
+    assert!((MathModule::stddev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]) - 2.0).abs() < 1e-12);
+    assert!(MathModule::mean(&[]).is_nan());
+    assert!(MathModule::stddev(&[]).is_nan());
+}
+
+#[test]
+fn test_repl_prints_integral_results_cleanly() {
+    use crate::{parser::Value, repl::Repl};
+
+    let mut output = Vec::new();
+    Repl::new().run("sqrt(4)\nsqrt(2)\nmax(1.5, 3)\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().map(|line| line.trim_start_matches("> ")).collect();
+    assert_eq!(lines[0], "2");
+    assert_eq!(lines[1], 2f64.sqrt().to_string());
+    assert_eq!(lines[2], "3");
+    assert!(!output.contains("2.0"));
+
+    assert_eq!(Value::Number(2.0).to_string(), "2");
+    assert_eq!(Value::Number(0.1 + 0.2).to_string(), "0.30000000000000004");
+}
+
+#[test]
+fn test_interpreter_is_quiet_by_default() {
+    use crate::{interpreter::Interpreter, parser::Value, runtime::run_source_with_interpreter};
+
+    let mut interpreter = Interpreter::new();
+    assert!(!interpreter.is_verbose());
+    assert!(Interpreter::new().with_verbose(true).is_verbose());
+
+    let result = run_source_with_interpreter("var x := 3\nvar y := max(x, 4) * 2\n# done", &mut interpreter);
+    assert_eq!(result.unwrap(), Value::Integer(8));
+    assert_eq!(interpreter.get_variable("x"), Some(3.0));
+}
+
+#[test]
+fn test_undefined_variable_message_is_english() {
+    use crate::runtime::run_source;
+
+    let error = run_source("y := missing + 1").unwrap_err();
+    assert_eq!(error.to_string(), "Runtime error: Undefined variable 'missing'");
+}
+
+#[test]
+fn test_runtime_error_display() {
+    use crate::interpreter::RuntimeError;
+
+    let display = |error: RuntimeError| error.to_string();
+
+    assert!(display(RuntimeError::UndefinedVariable("width".to_string())).contains("width"));
+    assert!(display(RuntimeError::TypeMismatch { op: "+".to_string() }).contains("'+'"));
+    assert!(display(RuntimeError::UnknownOperator("&".to_string())).contains("'&'"));
+    let arity = display(RuntimeError::WrongArity {
+        func: "atan2".to_string(),
+        expected: 2,
+        got: 3,
+    });
+    assert!(arity.contains("atan2") && arity.contains('2') && arity.contains('3'));
+    assert_eq!(display(RuntimeError::DivisionByZero), "Division by zero");
+    assert!(display(RuntimeError::IntegerOverflow { op: "*".to_string() }).contains("'*'"));
+    assert!(display(RuntimeError::StackUnderflow("store".to_string())).contains("store"));
+    let recursion = display(RuntimeError::RecursionLimitExceeded {
+        func: "f".to_string(),
+        limit: 200,
+    });
+    assert!(recursion.contains("'f'") && recursion.contains("200"));
+    assert!(display(RuntimeError::MissingArguments("sum".to_string())).contains("sum"));
+    assert!(display(RuntimeError::IterationLimitExceeded { limit: 10 }).contains("10"));
+    assert!(display(RuntimeError::AlreadyDeclared("x".to_string())).contains("'x'"));
+    assert!(display(RuntimeError::UndeclaredAssignment("x".to_string())).contains("'x'"));
+    let invalid = display(RuntimeError::InvalidExpression {
+        expr: "1 +".to_string(),
+        message: "Unexpected end of input".to_string(),
+    });
+    assert!(invalid.contains("1 +") && invalid.contains("Unexpected end of input"));
+
+    // Usable wherever a standard error is expected
+    let boxed: Box<dyn std::error::Error> = Box::new(RuntimeError::DivisionByZero);
+    assert_eq!(boxed.to_string(), "Division by zero");
+}
+
+#[test]
+fn test_division_by_zero_is_an_error() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    assert_eq!(eval("5 / 0"), Err(RuntimeError::DivisionByZero));
+    assert_eq!(eval("5 / 0.0"), Err(RuntimeError::DivisionByZero));
+    assert_eq!(eval("5.5 / (2 - 2)"), Err(RuntimeError::DivisionByZero));
+    match eval("5 / 0.0001") {
+        Ok(Value::Number(value)) => assert!((value - 50000.0).abs() < 1e-6),
+        other => panic!("Expected a number, got {:?}", other),
+    }
+
+    // Infinities are still available through explicit functions
+    assert!(matches!(eval("exp(1000)"), Ok(Value::Number(x)) if x.is_infinite()));
+}
+
+#[test]
+fn test_script_mixing_assignments_and_bare_expressions() {
+    use crate::{
+        compiler::{compile, Op},
+        parser::{parse_str, Value},
+        runtime::{run_source, run_with_result},
+    };
+
+    assert_eq!(run_source("3 + 4").unwrap(), Value::Integer(7));
+
+    let source = "# rectangle\nvar area := 3 * 4\narea + 1\n";
+    let statements = parse_str(source).unwrap();
+    assert_eq!(statements.len(), 3);
+    // Compiled one by one: the comment emits nothing, the declaration ends
+    // with a declaration and the bare expression leaves its sum on the stack
+    let last_ops: Vec<Option<Op>> = statements
+        .iter()
+        .map(|statement| compile(std::slice::from_ref(statement)).unwrap().last().cloned())
+        .collect();
+    assert_eq!(
+        last_ops,
+        vec![None, Some(Op::DeclareVar("area".to_string())), Some(Op::Add)]
+    );
+    assert_eq!(run_source(source).unwrap(), Value::Integer(13));
+
+    let path = std::env::temp_dir().join("oak_bare_expression.oak");
+    std::fs::write(&path, "3 + 4\n").unwrap();
+    assert_eq!(run_with_result(path.to_string_lossy().into_owned()).unwrap(), Value::Integer(7));
+}
+
+#[test]
+fn test_var_declaration_and_reassignment() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+
+    // Assigning before declaring fails and leaves the variable undefined
+    assert_eq!(eval("x := 1"), Err(RuntimeError::UndeclaredAssignment("x".to_string())));
+    assert_eq!(eval("x"), Err(RuntimeError::UndefinedVariable("x".to_string())));
+
+    assert_eq!(eval("var x := 1"), Ok(Value::Integer(1)));
+    assert_eq!(eval("x := x + 1"), Ok(Value::Integer(2)));
+
+    // Declaring the same name twice in one scope fails and keeps the old value
+    assert_eq!(eval("var x := 5"), Err(RuntimeError::AlreadyDeclared("x".to_string())));
+    assert_eq!(eval("x"), Ok(Value::Integer(2)));
+
+    // A function call has its own scope, where a global name can be declared again
+    eval("fn local(n) := var x := n * 10").unwrap();
+    assert_eq!(eval("local(4)"), Ok(Value::Integer(40)));
+    assert_eq!(eval("x"), Ok(Value::Integer(2)));
+    eval("fn again(n) := var n := 0").unwrap();
+    assert_eq!(eval("again(1)"), Err(RuntimeError::AlreadyDeclared("n".to_string())));
+    // Its parameters can be reassigned, the globals it reads cannot
+    eval("fn inc(n) := n := n + 1").unwrap();
+    assert_eq!(eval("inc(1)"), Ok(Value::Integer(2)));
+    eval("fn bump(n) := x := x + n").unwrap();
+    assert_eq!(eval("bump(1)"), Err(RuntimeError::GlobalAssignment("x".to_string())));
+    assert_eq!(eval("x"), Ok(Value::Integer(2)));
+
+    // Seeded variables count as declared
+    interpreter.set_variable("width", 3.0);
+    let value = parse_expr(&tokenize("width := width * 2")).unwrap().accept(&mut interpreter);
+    assert_eq!(value, Ok(Value::Number(6.0)));
+}
+
+#[test]
+fn test_vm_follows_interpreter_rules() {
+    use crate::{
+        compiler::{compile, Op, VM},
+        interpreter::{AngleMode, Interpreter},
+        parser::{parse_statements, Value},
+        tokenizer::tokenize,
+    };
+
+    let compile_source = |source: &str| compile(&parse_statements(&tokenize(source)).unwrap()).unwrap();
+    assert_eq!(
+        compile_source("var x := 1\nx := 2"),
+        vec![
+            Op::PushInt(1),
+            Op::DeclareVar("x".to_string()),
+            Op::PushInt(2),
+            Op::StoreVar("x".to_string()),
+        ]
+    );
+
+    // The same sources, statement by statement, give the same values and errors
+    let sources = [
+        "x := 1",
+        "var x := 1",
+        "var x := 2",
+        "x := x + 5",
+        "var y := x * 2",
+        "y := y - x",
+        "sin(90)",
+        "atan2(1, 1)",
+        "asin(1) + cos(0)",
+        "z := 3",
+    ];
+    for mode in [AngleMode::Radians, AngleMode::Degrees] {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_angle_mode(mode);
+        let mut vm = VM::new();
+        vm.set_angle_mode(mode);
+        for source in sources {
+            let statements = parse_statements(&tokenize(source)).unwrap();
+            let expected = statements[0].accept(&mut interpreter);
+            assert_eq!(vm.run(&compile(&statements).unwrap()), expected, "{} in {}", source, mode);
+        }
+        assert_eq!(vm.variables(), interpreter.variables());
+    }
+
+    let mut vm = VM::new();
+    vm.set_angle_mode(AngleMode::Degrees);
+    assert_eq!(vm.angle_mode(), AngleMode::Degrees);
+    assert_eq!(vm.run(&compile_source("sin(90)")), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_detokenize_round_trip() {
+    use crate::tokenizer::{detokenize, tokenize};
+
+    assert_eq!(detokenize(&tokenize("x:=3+4")), "x := 3 + 4");
+    assert_eq!(detokenize(&tokenize("var  y:=max( x,2.0 )*-z")), "var y := max(x, 2.0) * -z");
+    assert_eq!(detokenize(&tokenize("a - -5")), "a - -5");
+    assert_eq!(detokenize(&tokenize("s := \"hi there\" # note\nt")), "s := \"hi there\" # note\nt");
+    assert_eq!(detokenize(&[]), "");
+
+    let sources = [
+        "x:=3+4",
+        "fn f(a,b):=if a>=b then a else -(b^2)",
+        "while i<10 do i:=i+1",
+        "begin main\nvar r := 0x1F % 3\nend main",
+        "1e20 / 2.5e-3 + 99999999999999999999",
+        "- 5 + -x",
+        "#\n# comment\nsqrt(2)",
+    ];
+    for source in sources {
+        let tokens = tokenize(source);
+        assert_eq!(tokenize(&detokenize(&tokens)), tokens, "{}", source);
+    }
+}
+
+#[test]
+fn test_builtin_name_listing() {
+    use crate::{
+        math::{get_math_functions, math_constant_names, math_function_names},
+        repl::Repl,
+    };
+
+    let functions = math_function_names();
+    for name in ["sqrt", "sin", "atan2", "clamp", "sum", "stddev"] {
+        assert!(functions.contains(&name.to_string()), "{} missing from {:?}", name, functions);
+    }
+    assert!(functions.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", functions);
+    assert!(get_math_functions().keys().all(|name| functions.contains(name)));
+    assert_eq!(math_constant_names(), ["E", "LN_2", "PHI", "PI", "SQRT_2", "TAU"]);
+
+    let mut output = Vec::new();
+    Repl::new().run(":funcs\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Functions: abs, acos,"), "{}", output);
+    assert!(output.contains("Constants: E, LN_2, PHI, PI, SQRT_2, TAU"), "{}", output);
+}
+
+#[test]
+fn test_additional_math_constants() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Node, Value, Var},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let expected = [
+        ("TAU", 2.0 * std::f64::consts::PI),
+        ("SQRT_2", 2f64.sqrt()),
+        ("LN_2", 2f64.ln()),
+        ("PHI", 1.618033988749895),
+    ];
+    for (name, value) in expected {
+        match Var::parse(name.to_string()).accept(&mut interpreter) {
+            Ok(Value::Number(x)) => assert!((x - value).abs() < 1e-10, "{} = {}", name, x),
+            other => panic!("Expected a number for {}, got {:?}", name, other),
+        }
+    }
+
+    // Constants shadow variables of the same name
+    parse_expr(&tokenize("var TAU := 1")).unwrap().accept(&mut interpreter).unwrap();
+    match Var::parse("TAU".to_string()).accept(&mut interpreter) {
+        Ok(Value::Number(x)) => assert!((x - std::f64::consts::TAU).abs() < 1e-10),
+        other => panic!("Expected TAU, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_golden_ratio_partition() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule};
+
+    let phi = MathModule::phi();
+    for total in [1.0, 10.0, 37.5] {
+        let (a, b) = MathModule::golden_ratio_partition(total).unwrap();
+        assert!((a + b - total).abs() < 1e-10);
+        assert!((a / b - phi).abs() < 1e-10);
+        assert!(a > b);
+    }
+
+    match calc_architecture("golden_partition", &[10.0]) {
+        Ok(ArchitecturalResult::GoldenPartition(result)) => {
+            assert!((result.major - 6.180339887).abs() < 1e-9);
+            assert!((result.minor - 3.819660113).abs() < 1e-9);
+        }
+        other => panic!("Expected a golden partition result, got {:?}", other),
+    }
+    assert_eq!(
+        calc_architecture_command("golden_partition", &[10.0]),
+        "Golden ratio partition\nMajor segment: 6.180 m\nMinor segment: 3.820 m"
+    );
+
+    assert_eq!(
+        MathModule::golden_ratio_partition(0.0).unwrap_err(),
+        "Total length must be positive and finite"
+    );
+    assert!(MathModule::golden_ratio_partition(-5.0).is_err());
+    assert!(MathModule::golden_ratio_partition(f64::NAN).is_err());
+    assert!(calc_architecture("golden_partition", &[]).is_err());
+}
+
+#[test]
+fn test_building_area_and_volume() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule};
+
+    assert_eq!(MathModule::building_footprint_area(20.0, 15.0), Ok(300.0));
+    assert_eq!(MathModule::building_volume(20.0, 15.0, 30.0), Ok(9000.0));
+
+    match calc_architecture("footprint_area", &[20.0, 15.0]) {
+        Ok(ArchitecturalResult::FootprintArea(area)) => assert_eq!(area, 300.0),
+        other => panic!("Expected a footprint area result, got {:?}", other),
+    }
+    match calc_architecture("volume", &[20.0, 15.0, 30.0]) {
+        Ok(ArchitecturalResult::Volume(volume)) => assert_eq!(volume, 9000.0),
+        other => panic!("Expected a volume result, got {:?}", other),
+    }
+    assert_eq!(calc_architecture_command("footprint_area", &[20.0, 15.0]), "Footprint area: 300.00 m²");
+    assert_eq!(calc_architecture_command("volume", &[20.0, 15.0, 30.0]), "Building volume: 9000.00 m³");
+
+    assert_eq!(
+        MathModule::building_footprint_area(-20.0, 15.0).unwrap_err(),
+        "Building length must be positive and finite"
+    );
+    assert_eq!(
+        MathModule::building_volume(20.0, 15.0, -30.0).unwrap_err(),
+        "Building height must be positive and finite"
+    );
+    assert!(MathModule::building_volume(20.0, 0.0, 30.0).is_err());
+    assert!(calc_architecture("volume", &[20.0, 15.0]).is_err());
+}
+
+#[test]
+fn test_height_slenderness() {
+    use crate::math::{calc_architecture, calc_architecture_command, ArchitecturalResult, MathModule};
+
+    // Squat building: 30 m tall on a 15 m wide plan
+    let squat = MathModule::check_height_slenderness_compliance(20.0, 15.0, 30.0).unwrap();
+    assert!((squat.slenderness_ratio - 2.0).abs() < 1e-12);
+    assert_eq!(squat.max_ratio, 7.0);
+    assert!(squat.is_compliant);
+
+    // Tall narrow tower: 200 m tall on a 20 m wide plan
+    let tower = MathModule::check_height_slenderness_compliance(40.0, 20.0, 200.0).unwrap();
+    assert!((tower.slenderness_ratio - 10.0).abs() < 1e-12);
+    assert!(!tower.is_compliant);
+    // ...which passes with a looser limit
+    assert!(MathModule::check_height_slenderness_compliance_with_limit(40.0, 20.0, 200.0, 12.0)
+        .unwrap()
+        .is_compliant);
+
+    match calc_architecture("height_slenderness", &[40.0, 20.0, 200.0]) {
+        Ok(ArchitecturalResult::HeightSlenderness(result)) => assert!(!result.is_compliant),
+        other => panic!("Expected a height slenderness result, got {:?}", other),
+    }
+    assert_eq!(
+        calc_architecture_command("height_slenderness", &[20.0, 15.0, 30.0, 3.0]),
+        "Height slenderness compliance\nSlenderness ratio: 2.000\nMaximum ratio: 3.000\nThe building is compliant"
+    );
+
+    assert_eq!(
+        MathModule::calculate_height_slenderness(20.0, 0.0, 30.0).unwrap_err(),
+        "Building width must be positive and finite"
+    );
+    assert!(MathModule::calculate_height_slenderness(20.0, 15.0, 0.0).is_err());
+    assert!(MathModule::check_height_slenderness_compliance_with_limit(20.0, 15.0, 30.0, 0.0).is_err());
+    assert!(calc_architecture("height_slenderness", &[20.0, 15.0]).is_err());
+}
+
+#[test]
+fn test_nested_function_call_arguments() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::{parse_expr, BinOp, FunctionCall, Integer, Node, UnaryOp, Value},
+        tokenizer::tokenize,
+    };
+
+    let mut interpreter = Interpreter::new();
+
+    // Arguments built by hand: sqrt(3 + 1) and sqrt(abs(-16))
+    let sum = BinOp::parse(Box::new(Integer { value: 3 }), "+".to_string(), Box::new(Integer { value: 1 }));
+    let call = FunctionCall::parse("sqrt".to_string(), vec![Box::new(sum)]);
+    assert_eq!(call.accept(&mut interpreter), Ok(Value::Number(2.0)));
+
+    let negative = UnaryOp::parse("-".to_string(), Box::new(Integer { value: 16 }));
+    let abs = FunctionCall::parse("abs".to_string(), vec![Box::new(negative)]);
+    let call = FunctionCall::parse("sqrt".to_string(), vec![Box::new(abs)]);
+    assert_eq!(call.accept(&mut interpreter), Ok(Value::Number(4.0)));
+
+    // The same through the parser, including every kind of built-in
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+    assert_eq!(eval("sqrt(3+1)"), Ok(Value::Number(2.0)));
+    assert_eq!(eval("sqrt(abs(-16))"), Ok(Value::Number(4.0)));
+    assert_eq!(eval("hypot(sqrt(9), abs(2 - 6))"), Ok(Value::Number(5.0)));
+    assert_eq!(eval("clamp(max(1, 2, 3) * 4, min(0, 1), pow(2, 3))"), Ok(Value::Number(8.0)));
+    assert_eq!(eval("sum(sqrt(16), abs(-1), 2 ^ 2)"), Ok(Value::Number(9.0)));
+
+    // Errors inside a nested argument are propagated
+    assert_eq!(eval("sqrt(abs(missing))"), Err(RuntimeError::UndefinedVariable("missing".to_string())));
+    assert_eq!(eval("sqrt(abs(1, 2))"), Err(RuntimeError::WrongArity {
+        func: "abs".to_string(),
+        expected: 1,
+        got: 2,
+    }));
+}
+
+#[test]
+fn test_tokenize_dot() {
+    use crate::{
+        parser::{parse_expr, ScriptError},
+        tokenizer::{detokenize, tokenize, Token},
+    };
+
+    // A dot within or right after digits is a decimal point
+    assert_eq!(tokenize("3.5"), vec![Token::Number(3.5)]);
+    assert_eq!(tokenize("3."), vec![Token::Number(3.0)]);
+    assert_eq!(tokenize("3. + 1"), vec![Token::Number(3.0), Token::Operator("+".to_string()), Token::Integer(1)]);
+
+    // Anywhere else it is a dot of its own
+    assert_eq!(
+        tokenize("a.b"),
+        vec![
+            Token::Identifier("a".to_string()),
+            Token::Dot,
+            Token::Identifier("b".to_string()),
+        ]
+    );
+    assert_eq!(tokenize("f(x).y")[4], Token::Dot);
+    assert_eq!(tokenize(" . "), vec![Token::Dot]);
+
+    // Member access is not part of the language yet
+    assert!(matches!(parse_expr(&tokenize("a.b")), Err(ScriptError::Parse(_))));
+
+    assert_eq!(detokenize(&tokenize("a . b")), "a.b");
+    for source in ["a.b", "x.1", "2 .a"] {
+        let tokens = tokenize(source);
+        assert_eq!(tokenize(&detokenize(&tokens)), tokens, "{}", source);
+    }
+}
+
+#[test]
+fn test_tokenize_leading_dot_numbers() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::{parse_expr, Value},
+        tokenizer::{tokenize, Token},
+    };
+
+    assert_eq!(tokenize(".5"), vec![Token::Number(0.5)]);
+    assert_eq!(tokenize("-.5"), vec![Token::Number(-0.5)]);
+    assert_eq!(tokenize("(.25)")[1], Token::Number(0.25));
+    assert_eq!(tokenize(".5e2"), vec![Token::Number(50.0)]);
+
+    let mut interpreter = Interpreter::new();
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+    assert_eq!(eval(".5"), Ok(Value::Number(0.5)));
+    assert_eq!(eval(".25 + .25"), Ok(Value::Number(0.5)));
+    assert_eq!(eval("2*.5"), Ok(Value::Number(1.0)));
+
+    // A dot without a digit after it, or attached to a name, is not a number
+    assert_eq!(tokenize("."), vec![Token::Dot]);
+    assert_eq!(tokenize(". 5"), vec![Token::Dot, Token::Integer(5)]);
+    assert_eq!(tokenize("a.5")[1], Token::Dot);
+    assert_eq!(tokenize("._5"), vec![Token::Dot, Token::Unknown("_5".to_string())]);
+}
+
+#[test]
+fn test_evaluation_depth_limit() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError, DEFAULT_MAX_DEPTH},
+        parser::{parse_expr, BinOp, Integer, Node, Value},
+        tokenizer::tokenize,
+    };
+
+    // 1 + (1 + (1 + ...)), nested `depth` times
+    let nested = |depth: usize| {
+        let mut node: Box<dyn Node> = Box::new(Integer { value: 1 });
+        for _ in 0..depth {
+            node = Box::new(BinOp::parse(Box::new(Integer { value: 1 }), "+".to_string(), node));
+        }
+        node
+    };
+
+    let mut interpreter = Interpreter::new();
+    assert_eq!(nested(900).accept(&mut interpreter), Ok(Value::Integer(901)));
+    assert_eq!(
+        nested(5000).accept(&mut interpreter),
+        Err(RuntimeError::RecursionLimitExceeded {
+            func: "expression".to_string(),
+            limit: DEFAULT_MAX_DEPTH,
+        })
+    );
+    // The depth is back to zero after an error
+    assert_eq!(nested(900).accept(&mut interpreter), Ok(Value::Integer(901)));
+
+    interpreter.set_max_depth(10);
+    let mut eval = |source: &str| parse_expr(&tokenize(source)).unwrap().accept(&mut interpreter);
+    assert!(eval("((((1 + 2) * 3) - 4) / 5)").is_ok());
+    assert!(matches!(
+        eval("1 + (1 + (1 + (1 + (1 + (1 + (1 + (1 + (1 + (1 + (1 + 1))))))))))"),
+        Err(RuntimeError::RecursionLimitExceeded { limit: 10, .. })
+    ));
+}
+
+#[test]
+fn test_eval_all_shares_state() {
+    use crate::{
+        interpreter::{Interpreter, RuntimeError},
+        parser::Value,
+    };
+
+    let mut interpreter = Interpreter::new();
+    let results = interpreter.eval_all(&["var x := 6", "x * 7", "y + 1", "x +", "sqrt(x + 3)"]);
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[0], Ok(Value::Integer(6)));
+    assert_eq!(results[1], Ok(Value::Integer(42)));
+    // Failures are reported per source and do not stop the rest
+    assert_eq!(results[2], Err(RuntimeError::UndefinedVariable("y".to_string())));
+    assert!(matches!(&results[3], Err(RuntimeError::InvalidExpression { expr, .. }) if expr == "x +"));
+    assert_eq!(results[4], Ok(Value::Number(3.0)));
+
+    // The state is kept for later calls
+    assert_eq!(interpreter.get_variable("x"), Some(6.0));
+    assert!(interpreter.eval_all(&[]).is_empty());
+}
+
+#[test]
+fn test_eval_convenience_function() {
+    use crate::{
+        eval,
+        interpreter::RuntimeError,
+        parser::{parse_expr_str, parse_str, Value},
+        tokenizer::LexError,
+        OakError,
+    };
+
+    assert_eq!(eval("2 + 3 * 4").unwrap(), Value::Number(14.0));
+    assert_eq!(eval("sqrt(16) + PI - PI").unwrap(), Value::Number(4.0));
+
+    assert_eq!(
+        eval("@"),
+        Err(OakError::Lex(LexError::UnexpectedInput {
+            text: "@".to_string(),
+            position: 0,
+        }))
+    );
+    assert!(matches!(eval("2 +"), Err(OakError::Parse(_))));
+    assert!(matches!(eval("1 2"), Err(OakError::Parse(_))));
+    assert_eq!(eval("x"), Err(OakError::Runtime(RuntimeError::UndefinedVariable("x".to_string()))));
+    assert_eq!(eval("nope(1)"), Err(OakError::Runtime(RuntimeError::UnknownFunction("nope".to_string()))));
+    assert_eq!(eval("1 / 0").unwrap_err().to_string(), "Runtime error: Division by zero");
+
+    // Expressions and scripts are tokenized and checked the same way
+    assert_eq!(parse_expr_str("1 + @").err().map(|e| e.to_string()), parse_str("1 + @").err().map(|e| e.to_string()));
+    assert!(parse_expr_str("x := 1; 2").is_err());
+}
+
+#[test]
+fn test_pow_checked() {
+    use crate::{
+        interpreter::Interpreter,
+        math::MathModule,
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+    };
+
+    assert_eq!(MathModule::pow_checked(2.0, 10.0), Ok(1024.0));
+    assert_eq!(MathModule::pow_checked(10.0, -400.0), Ok(0.0));
+    assert_eq!(MathModule::pow_checked(10.0, 400.0).unwrap_err(), "10 ^ 400 overflows");
+    assert!(MathModule::pow_checked(-10.0, 401.0).is_err());
+    assert_eq!(MathModule::pow_checked(0.0, -1.0).unwrap_err(), "0 ^ -1 is a division by zero");
+    assert_eq!(MathModule::pow_checked(-0.0, -3.0).unwrap_err(), "-0 ^ -3 is a division by zero");
+    assert_eq!(MathModule::pow_checked(0.0, 0.0), Ok(1.0));
+    // Only overflow is an error, infinite inputs and NaN pass through
+    assert_eq!(MathModule::pow_checked(f64::INFINITY, 2.0), Ok(f64::INFINITY));
+    assert!(MathModule::pow_checked(-8.0, 0.5).unwrap().is_nan());
+
+    // The operator stays lenient
+    let mut interpreter = Interpreter::new();
+    let result = parse_expr(&tokenize("10.0 ^ 400")).unwrap().accept(&mut interpreter);
+    assert!(matches!(result, Ok(Value::Number(x)) if x.is_infinite()));
+}
+
+#[test]
+#[allow(clippy::approx_constant)]
+fn test_round_to_and_repl_precision() {
+    use crate::{math::MathModule, repl::Repl};
+
+    assert_eq!(MathModule::round_to(3.14159, 2), Ok(3.14));
+    assert_eq!(MathModule::round_to(2.5, 0), Ok(3.0));
+    assert_eq!(MathModule::round_to(-2.5, 0), Ok(-3.0));
+    assert_eq!(MathModule::round_to(1234.5678, 1), Ok(1234.6));
+    assert_eq!(MathModule::round_to(1e300, 20), Ok(1e300));
+    assert_eq!(
+        MathModule::round_to(3.14159, -1).unwrap_err(),
+        "Number of decimals must not be negative"
+    );
+
+    let input = "PI\n:precision 3\nPI\n10 / 4\n7\n:precision\n:precision -2\n:precision off\nPI\n";
+    let mut output = Vec::new();
+    Repl::new().run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().map(|line| line.trim_start_matches("> ")).collect();
+    assert_eq!(lines[0], std::f64::consts::PI.to_string());
+    assert_eq!(lines[1], "Precision: 3 decimals");
+    assert_eq!(lines[2], "3.142");
+    assert_eq!(lines[3], "2");
+    assert_eq!(lines[4], "7");
+    assert_eq!(lines[5], "Precision: 3 decimals");
+    assert!(lines[6].starts_with("Invalid precision '-2'"));
+    assert_eq!(lines[7], "Precision: full");
+    assert_eq!(lines[8], std::f64::consts::PI.to_string());
+
+    let mut output = Vec::new();
+    Repl::new().with_precision(Some(1)).run("2 / 3.0\n".as_bytes(), &mut output).unwrap();
+    assert!(String::from_utf8(output).unwrap().contains("0.7"));
+}
+
+#[test]
+fn test_semicolon_separated_statements() {
+    use crate::{
+        compiler::{compile, Op, VM},
+        parser::{parse_str, Value},
+        repl::Repl,
+        runtime::run_source,
+        tokenizer::{detokenize, tokenize, Token},
+    };
+
+    assert_eq!(tokenize("1;-2")[1..], [Token::Semicolon, Token::Integer(-2)]);
+    assert_eq!(detokenize(&tokenize("x:=1 ;y:=2")), "x := 1; y := 2");
+
+    assert_eq!(parse_str("var x := 1; var y := 2; x + y").unwrap().len(), 3);
+    assert_eq!(parse_str("var x := 1; var y := 2; x + y;").unwrap().len(), 3);
+    assert_eq!(parse_str(";; 1;;").unwrap().len(), 1);
+    assert_eq!(run_source("var x := 1; var y := 2; x + y").unwrap(), Value::Integer(3));
+
+    let mut repl = Repl::new();
+    assert_eq!(repl.eval_line("var x := 1; var y := 2; x + y").unwrap(), Value::Integer(3));
+    // A trailing semicolon leaves the line without a value, as it does a script
+    assert_eq!(repl.eval_line("x := 5; y := 6;").unwrap(), Value::None);
+    assert_eq!(run_source("var y := 6;").unwrap(), Value::None);
+    assert_eq!(run_source("var y := 6;\n# done\n").unwrap(), Value::None);
+    assert_eq!(repl.eval_line("x * y").unwrap(), Value::Integer(30));
+    // Each part must still be a single expression
+    assert!(repl.eval_line("1 2; 3").is_err());
+    assert!(run_source("1 2; 3").is_err());
+    assert!(repl.eval_line("1 +; 3").is_err());
+    assert!(run_source("1 +; 3").is_err());
+
+    // The VM drops the value of a statement ended by `;`
+    let ops = compile(&parse_str("var x := 1; x + 1;").unwrap()).unwrap();
+    assert_eq!(ops.last(), Some(&Op::Pop));
+    let mut vm = VM::new();
+    assert_eq!(vm.run(&ops), Ok(Value::None));
+    assert_eq!(vm.variables()["x"], Value::Integer(1));
+
+    let mut output = Vec::new();
+    Repl::new().run("var a := 2; a * 3\nvar b := 4;\nb\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().map(|line| line.trim_start_matches("> ")).collect();
+    assert_eq!(lines[0], "6");
+    assert_eq!(lines[1], "4");
+}
+
+#[test]
+fn test_repl_lines_follow_script_grammar() {
+    use crate::{interpreter::Interpreter, parser::Value, repl::Repl, runtime::run_source_with_interpreter};
+
+    // Each line gives the same value in the REPL as the same source run as a script
+    let lines = [
+        "# setup",
+        "var x := 2 # two",
+        "x * 3; # done",
+        "fn f(a) := a + x",
+        "f(1);; f(2)",
+        ";",
+        "x := 4; f(x)",
+    ];
+    let mut repl = Repl::new();
+    let mut interpreter = Interpreter::new();
+    for line in lines {
+        let expected = run_source_with_interpreter(line, &mut interpreter).unwrap();
+        assert_eq!(repl.eval_line(line).unwrap(), expected, "{}", line);
+    }
+    assert_eq!(repl.eval_line("# only a comment").unwrap(), Value::None);
+    assert_eq!(repl.eval_line("x * 3 # trailing").unwrap(), Value::Integer(12));
+
+    let mut output = Vec::new();
+    Repl::new().run("# note\nvar y := 5 # five\ny\n".as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(!output.contains("Error"), "{}", output);
+    assert!(output.contains("5\n"), "{}", output);
+}
+
+#[test]
+fn test_percentages() {
+    use crate::{eval, math::MathModule, parser::Value};
+
+    assert_eq!(MathModule::percent_of(25.0, 200.0), Ok(12.5));
+    assert_eq!(MathModule::percent_of(-30.0, 120.0), Ok(-25.0));
+    assert_eq!(
+        MathModule::percent_of(25.0, 0.0).unwrap_err(),
+        "Cannot take a percentage of a zero whole"
+    );
+    assert_eq!(MathModule::apply_percent(100.0, 10.0), 110.0);
+    assert_eq!(MathModule::apply_percent(200.0, -25.0), 150.0);
+    assert_eq!(MathModule::percent_to_bps(1.25), 125.0);
+    assert_eq!(MathModule::bps_to_percent(50.0), 0.5);
+
+    // Also callable from scripts, where a zero whole gives NaN
+    assert_eq!(eval("percent_of(25, 200)").unwrap(), Value::Number(12.5));
+    assert_eq!(eval("apply_percent(100, 10)").unwrap(), Value::Number(110.0));
+    assert_eq!(eval("bps_to_percent(percent_to_bps(3))").unwrap(), Value::Number(3.0));
+    assert!(matches!(eval("percent_of(1, 0)"), Ok(Value::Number(x)) if x.is_nan()));
+}
+
+#[test]
+fn test_string_comparison() {
+    use crate::{eval, interpreter::RuntimeError, parser::Value, OakError};
+
+    assert_eq!(eval("\"a\" == \"a\"").unwrap(), Value::Bool(true));
+    assert_eq!(eval("\"a\" == \"b\"").unwrap(), Value::Bool(false));
+    assert_eq!(eval("\"a\" != \"b\"").unwrap(), Value::Bool(true));
+    // Ordering is lexicographic, by Unicode code point
+    assert_eq!(eval("\"a\" < \"b\"").unwrap(), Value::Bool(true));
+    assert_eq!(eval("\"b\" <= \"a\"").unwrap(), Value::Bool(false));
+    assert_eq!(eval("\"abc\" > \"ab\"").unwrap(), Value::Bool(true));
+    assert_eq!(eval("\"B\" < \"a\"").unwrap(), Value::Bool(true));
+    assert_eq!(eval("\"10\" < \"9\"").unwrap(), Value::Bool(true));
+
+    // Strings are still not numbers
+    assert_eq!(
+        eval("\"1\" == 1").unwrap_err(),
+        OakError::Runtime(RuntimeError::TypeMismatch { op: "==".to_string() })
+    );
+    assert_eq!(
+        eval("\"a\" + \"b\"").unwrap_err(),
+        OakError::Runtime(RuntimeError::TypeMismatch { op: "+".to_string() })
+    );
+}
+
+#[test]
+fn test_conditional_operator() {
+    use crate::{
+        eval,
+        interpreter::RuntimeError,
+        parser::{parse_expr, Value},
+        repl::{is_complete, Repl},
+        tokenizer::{detokenize, tokenize, Token},
+        OakError,
+    };
+
+    assert_eq!(
+        tokenize("c?-1:2"),
+        vec![
+            Token::Identifier("c".to_string()),
+            Token::Question,
+            Token::Integer(-1),
+            Token::Colon,
+            Token::Integer(2),
+        ]
+    );
+    assert_eq!(tokenize("x := 1")[1], Token::Assign);
+    assert_eq!(detokenize(&tokenize("c?1:2")), "c ? 1 : 2");
+
+    // The conditional binds looser than comparisons, even in its last branch
+    assert_eq!(eval("3 < 4 ? 1 : 0 == 1").unwrap(), Value::Integer(1));
+    assert_eq!(eval("(3 < 4 ? 1 : 0) == 1").unwrap(), Value::Bool(true));
+    assert_eq!(eval("1 + 1 > 3 ? 10 : 20 * 2").unwrap(), Value::Integer(40));
+    // Right-associative
+    assert_eq!(eval("1 > 2 ? 1 : 2 > 3 ? 2 : 3").unwrap(), Value::Integer(3));
+    assert_eq!(eval("max(1 < 2 ? 5 : 6, 2)").unwrap(), Value::Integer(5));
+
+    // The unchosen branch is not evaluated
+    assert_eq!(eval("1 < 2 ? 7 : 1 / 0").unwrap(), Value::Integer(7));
+    assert_eq!(eval("1 > 2 ? missing : 8").unwrap(), Value::Integer(8));
+    assert_eq!(
+        eval("1 > 2 ? 7 : 1 / 0").unwrap_err(),
+        OakError::Runtime(RuntimeError::DivisionByZero)
+    );
+    // The condition must be a boolean
+    assert!(eval("1 ? 2 : 3").is_err());
+
+    assert!(parse_expr(&tokenize("1 < 2 ? 3")).is_err());
+    assert!(parse_expr(&tokenize("1 < 2 : 3")).is_err());
+
+    assert!(!is_complete("x < 0 ?"));
+    assert!(!is_complete("x < 0 ? 1 :"));
+    let mut repl = Repl::new();
+    assert_eq!(repl.eval_line("var x := -4; var s := x < 0 ? -1 : 1; s").unwrap(), Value::Integer(-1));
+}
+
+#[test]
+fn test_safe_divide() {
+    use crate::{eval, math::MathModule, parser::Value};
+
+    assert_eq!(MathModule::safe_divide(10.0, 2.0, -1.0), 5.0);
+    assert_eq!(MathModule::safe_divide(10.0, 0.0, -1.0), -1.0);
+    assert_eq!(MathModule::safe_divide(0.0, 0.0, -1.0), -1.0);
+    assert_eq!(MathModule::safe_divide(10.0, -0.0, 3.0), 3.0);
+
+    // Never infinite, even when the quotient overflows
+    for (a, b) in [(1.0, 0.0), (-1.0, 0.0), (f64::MAX, 0.5), (1.0, 1e-320), (f64::INFINITY, 2.0)] {
+        let result = MathModule::safe_divide(a, b, 0.0);
+        assert!(result.is_finite(), "safe_divide({}, {}) gave {}", a, b, result);
+    }
+
+    // Dispatched as a three-argument function
+    assert_eq!(eval("safe_divide(10, 2, -1)").unwrap(), Value::Number(5.0));
+    assert_eq!(eval("safe_divide(10, 0, -1)").unwrap(), Value::Number(-1.0));
+    assert_eq!(eval("safe_divide(10, 0, -1) == -1").unwrap(), Value::Bool(true));
+    assert!(eval("safe_divide(10, 0)").is_err());
+}
+
+#[test]
+fn test_function_arity() {
+    use crate::{
+        eval,
+        interpreter::{Interpreter, RuntimeError},
+        math::{math_function_names, MathModule, AGGREGATE_FUNCTIONS},
+        parser::{parse_expr, Value},
+        tokenizer::tokenize,
+        OakError,
+    };
+
+    assert_eq!(MathModule::function_arity("sin"), Some(1));
+    assert_eq!(MathModule::function_arity("pow"), Some(2));
+    assert_eq!(MathModule::function_arity("clamp"), Some(3));
+    assert_eq!(MathModule::function_arity("safe_divide"), Some(3));
+    assert_eq!(MathModule::function_arity("missing"), None);
+    assert_eq!(MathModule::function_arity("calc_architecture"), None);
+    // Aggregates take any number of arguments
+    assert_eq!(MathModule::function_arity("max"), None);
+
+    // Every built-in either has a fixed arity or is an aggregate
+    for name in math_function_names() {
+        assert!(
+            MathModule::function_arity(&name).is_some() || AGGREGATE_FUNCTIONS.contains(&name.as_str()),
+            "{} has no arity",
+            name
+        );
+    }
+
+    // The count is checked before any argument is evaluated
+    assert_eq!(
+        eval("pow(1 / 0)").unwrap_err(),
+        OakError::Runtime(RuntimeError::WrongArity {
+            func: "pow".to_string(),
+            expected: 2,
+            got: 1,
+        })
+    );
+    assert_eq!(
+        eval("sqrt(missing, 2)").unwrap_err(),
+        OakError::Runtime(RuntimeError::WrongArity {
+            func: "sqrt".to_string(),
+            expected: 1,
+            got: 2,
+        })
+    );
+    assert_eq!(eval("max(1, 5, 3)").unwrap(), Value::Integer(5));
+
+    // A registered function takes a single argument, even in place of a binary built-in
+    fn double(x: f64) -> f64 {
+        x * 2.0
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.register_function("pow", double);
+    let call = parse_expr(&tokenize("pow(4)")).unwrap();
+    assert_eq!(call.accept(&mut interpreter).unwrap(), Value::Number(8.0));
+}
+
+#[test]
+fn test_repl_load_command() {
+    use crate::{parser::Value, repl::Repl};
+
+    let path = std::env::temp_dir().join("oak_repl_load.oak");
+    std::fs::write(&path, "# Shared values\nvar x := 42\nfn twice(n) := n * 2\n").unwrap();
+    let broken = std::env::temp_dir().join("oak_repl_load_broken.oak");
+    std::fs::write(&broken, "var y := (1 +\n").unwrap();
+    let missing = std::env::temp_dir().join("oak_repl_load_missing.oak");
+
+    let input = format!(
+        ":load {}\nx\ntwice(x)\n:load {}\n:load {}\n:load\nx + 1\n",
+        path.display(),
+        missing.display(),
+        broken.display()
+    );
+    let mut repl = Repl::new();
+    let mut output = Vec::new();
+    repl.run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().map(|line| line.trim_start_matches("> ")).collect();
+
+    // The comment, the declaration and the definition
+    assert_eq!(lines[0], format!("Loaded 3 statement(s) from {}", path.display()));
+    assert_eq!(lines[1], "42");
+    assert_eq!(lines[2], "84");
+    // Errors are reported and the session goes on
+    assert!(lines[3].starts_with("Error: IO error"), "{}", output);
+    assert!(lines[4].starts_with("Error: Parse error"), "{}", output);
+    assert_eq!(lines[5], "Missing file name, usage: :load <file>");
+    assert_eq!(lines[6], "43");
+
+    // Loaded variables are declared in the session, so they can be reassigned
+    assert_eq!(repl.eval_line("x := 7").unwrap(), Value::Integer(7));
+    // Loading the same file again assigns them
+    assert_eq!(repl.load_file(&path.display().to_string()).unwrap(), 3);
+    assert_eq!(repl.eval_line("x").unwrap(), Value::Integer(42));
+    // Only while loading
+    assert_eq!(
+        repl.eval_line("var x := 1").unwrap_err().to_string(),
+        "Runtime error: Variable 'x' is already declared"
+    );
+}
+
+#[test]
+fn test_repl_save_command() {
+    use crate::{parser::Value, repl::Repl};
+
+    let path = std::env::temp_dir().join("oak_repl_save.oak");
+    let mut repl = Repl::new();
+    let input = format!(
+        "var sum := 0.1 + 0.2\nvar count := 7\nvar tiny := -1e-300\nvar whole := 2.0\nvar bad := sqrt(-1)\n:save {}\n:save\n",
+        path.display()
+    );
+    let mut output = Vec::new();
+    repl.run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(&format!("Saved 4 variable(s) to {}", path.display())), "{}", output);
+    assert!(output.contains("Skipped 'bad', its value has no literal form"), "{}", output);
+    assert!(output.contains("Missing file name, usage: :save <file>"), "{}", output);
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "var count := 7\nvar sum := 0.30000000000000004\nvar tiny := -1e-300\nvar whole := 2.0\n"
+    );
+
+    // Reloading gives back the exact values, with their types
+    let mut fresh = Repl::new();
+    assert_eq!(fresh.load_file(&path.display().to_string()).unwrap(), 4);
+    for name in ["count", "sum", "tiny", "whole"] {
+        let saved = repl.eval_line(name).unwrap();
+        let loaded = fresh.eval_line(name).unwrap();
+        match (&saved, &loaded) {
+            (Value::Integer(a), Value::Integer(b)) => assert_eq!(a, b),
+            (Value::Number(a), Value::Number(b)) => assert_eq!(a.to_bits(), b.to_bits()),
+            _ => panic!("{} changed from {:?} to {:?}", name, saved, loaded),
+        }
+    }
+    assert!(fresh.eval_line("bad").is_err());
+
+    // Unwritable locations are reported without leaving the session
+    let mut output = Vec::new();
+    let unwritable = std::env::temp_dir().join("oak_missing_dir").join("nested").join("vars.oak");
+    repl.run(format!(":save {}\ncount\n", unwritable.display()).as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Error: Could not write"), "{}", output);
+    assert!(output.contains("7\n"), "{}", output);
+}
+
+#[test]
+fn test_repl_save_then_load_in_same_session() {
+    use crate::{parser::Value, repl::Repl};
+
+    let path = std::env::temp_dir().join("oak_repl_save_load.oak");
+    let input = format!(
+        "var a := 1.5\nvar b := 2\n:save {0}\na := 10\nvar c := 3\n:load {0}\na + b + c\n",
+        path.display()
+    );
+    let mut repl = Repl::new();
+    let mut output = Vec::new();
+    repl.run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(!output.contains("Error"), "{}", output);
+    assert!(output.contains(&format!("Loaded 2 statement(s) from {}", path.display())), "{}", output);
+    // The saved values are back, variables declared after saving are kept
+    assert!(output.ends_with("6.5\n> "), "{}", output);
+    assert_eq!(repl.eval_line("a").unwrap(), Value::Number(1.5));
+    // The load does not lift the rule for the lines after it
+    assert!(repl.eval_line("var a := 5").is_err());
+
+    // A file declaring one of its own variables twice fails as it does with `oak file.oak`
+    let twice = std::env::temp_dir().join("oak_repl_load_twice.oak");
+    std::fs::write(&twice, "var d := 1\nvar d := 2\n").unwrap();
+    assert!(Repl::new().load_file(twice.to_str().unwrap()).is_err());
+    // Even when the session already had it before the load
+    std::fs::write(&twice, "var a := 1\nvar a := 2\n").unwrap();
+    let error = repl.load_file(twice.to_str().unwrap()).unwrap_err();
+    assert_eq!(error.to_string(), "Runtime error: Variable 'a' is already declared");
+}
+
+#[test]
+fn test_value_ordering() {
+    use crate::parser::Value;
+    use std::cmp::Ordering;
+
+    assert!(Value::Number(1.0) < Value::Number(2.0));
+    assert!(Value::Integer(3) > Value::Integer(-3));
+    assert!(Value::String("apple".to_string()) < Value::String("banana".to_string()));
+    assert!(Value::Bool(false) < Value::Bool(true));
+
+    // Consistent with equality, integers and floats compare by value
+    assert_eq!(Value::Integer(2).partial_cmp(&Value::Number(2.0)), Some(Ordering::Equal));
+    assert!(Value::Integer(2) < Value::Number(2.5));
+    assert!(Value::Number(-0.5) < Value::Integer(0));
+    assert_eq!(Value::None.partial_cmp(&Value::None), Some(Ordering::Equal));
+
+    // Different kinds of values and NaN are unordered
+    assert_eq!(Value::Number(1.0).partial_cmp(&Value::String("1".to_string())), None);
+    assert_eq!(Value::String("a".to_string()).partial_cmp(&Value::Integer(1)), None);
+    assert_eq!(Value::Bool(true).partial_cmp(&Value::None), None);
+    assert_eq!(Value::Number(f64::NAN).partial_cmp(&Value::Number(1.0)), None);
+    // so neither `<` nor `>=` holds between them
+    assert!(!(Value::Number(1.0) < Value::String("2".to_string())
+        || Value::Number(1.0) >= Value::String("2".to_string())));
+
+    let mut values = vec![Value::Number(2.5), Value::Integer(-1), Value::Number(0.5), Value::Integer(2)];
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(values, vec![Value::Integer(-1), Value::Number(0.5), Value::Integer(2), Value::Number(2.5)]);
+}
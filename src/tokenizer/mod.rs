@@ -1,28 +1,79 @@
 // Tokenizer
+use thiserror::Error;
+
+/// Errors found while scanning source text
+///
+/// Positions are character offsets into the source.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LexError {
+    #[error("Unterminated string literal starting at position {0}")]
+    UnterminatedString(usize),
+    #[error("Unexpected '{text}' at position {position}")]
+    UnexpectedInput { text: String, position: usize },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Var,
+    /// Control flow and definition keywords
+    If,
+    Then,
+    Else,
+    While,
+    Do,
+    Fn,
+    Return,
     Identifier(String),
     Assign,
+    /// Number literal without a decimal point or exponent, e.g. `42` or `0xFF`
+    Integer(i64),
     Number(f64),
     StringLiteral(String),
     Operator(String),
+    LParen,
+    RParen,
+    Comma,
+    /// A `.` that is not a decimal point, e.g. in `a.b`
+    Dot,
+    /// Separates statements written on the same line
+    Semicolon,
+    /// A line break, which ends a statement outside parentheses
+    Newline,
+    /// The `?` and `:` of a conditional expression `cond ? a : b`
+    Question,
+    Colon,
     BeginSection(String),
     EndSection(String),
     Comment(String),
     Unknown(String),
+    /// Source that could not be scanned; reported by the parser
+    Error(LexError),
 }
 
 pub fn tokenize(source: &str) -> Vec<Token> {
+    tokenize_with_positions(source)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect()
+}
+
+/// Tokenize `source`, pairing each token with the character offset where it starts
+pub fn tokenize_with_positions(source: &str) -> Vec<(Token, usize)> {
     let mut tokens = Vec::new();
+    let mut positions = Vec::new();
     // Manual tokenization without regex (finite state machine) approach
     let chars: Vec<char> = source.chars().collect();
     let mut pos = 0;
 
     while pos < chars.len() {
         let c = chars[pos];
+        let token_start = pos;
 
         match c {
+            '\n' => {
+                tokens.push(Token::Newline);
+                pos += 1;
+            }
             // If the character si whitespace it continues until the
             // tokenizer encounters a character to match
             c if c.is_whitespace() => pos += 1,
@@ -31,45 +82,136 @@ pub fn tokenize(source: &str) -> Vec<Token> {
                 tokens.push(Token::Assign);
                 pos += 2;
             }
+            // Two-character comparison operators are matched before the single-character ones
+            '=' | '!' | '<' | '>' if pos + 1 < chars.len() && chars[pos + 1] == '=' => {
+                tokens.push(Token::Operator(format!("{}=", c)));
+                pos += 2;
+            }
+            '<' | '>' => {
+                tokens.push(Token::Operator(c.to_string()));
+                pos += 1;
+            }
+            // A minus sign in unary position (start of input, or right after an operator,
+            // an assignment, an opening paren, a comma, a semicolon, a line break, a `?`
+            // or `:` or a section start) is folded into the number that follows it.
+            // Not when that number is raised to a power: `-2 ^ 2` is `-(2 ^ 2)`, as
+            // `-x ^ 2` is, so the minus stays a prefix operator.
+            '-' if starts_number(&chars, pos + 1)
+                && is_unary_position(&tokens)
+                && !followed_by_power(&chars, scan_number(&chars, pos).1) =>
+            {
+                let (token, end) = scan_number(&chars, pos);
+                tokens.push(token);
+                pos = end;
+            }
             '+' | '-' | '*' | '/' | '%' | '^' => {
                 tokens.push(Token::Operator(c.to_string()));
                 pos += 1;
             }
+            // Line comments run from `#` to the end of the line
+            '#' => {
+                pos += 1;
+                let start = pos;
+                while pos < chars.len() && chars[pos] != '\n' {
+                    pos += 1;
+                }
+                let comment: String = chars[start..pos].iter().collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                pos += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                pos += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                pos += 1;
+            }
+            ';' => {
+                tokens.push(Token::Semicolon);
+                pos += 1;
+            }
+            '?' => {
+                tokens.push(Token::Question);
+                pos += 1;
+            }
+            // Not followed by `=`, so not an assignment
+            ':' => {
+                tokens.push(Token::Colon);
+                pos += 1;
+            }
+            // A `.` followed by digits starts a number like `.5`, unless it is
+            // attached to a name or a closing paren, as in `a.5`
+            '.' if starts_number(&chars, pos) && !follows_operand(&chars, pos) => {
+                let (token, end) = scan_number(&chars, pos);
+                tokens.push(token);
+                pos = end;
+            }
+            // A `.` right after digits was consumed by the number it belongs to
+            '.' => {
+                tokens.push(Token::Dot);
+                pos += 1;
+            }
             '"' => {
+                let quote = pos;
                 pos += 1;
                 let start = pos;
                 while pos < chars.len() && chars[pos] != '"' {
                     pos += 1;
                 }
-                let literal: String = chars[start..pos].iter().collect();
-                tokens.push(Token::StringLiteral(literal));
-                pos += 1; // consumes closing quote
+                if pos == chars.len() {
+                    // No closing quote before the end of input
+                    tokens.push(Token::Error(LexError::UnterminatedString(quote)));
+                } else {
+                    let literal: String = chars[start..pos].iter().collect();
+                    tokens.push(Token::StringLiteral(literal));
+                    pos += 1; // consumes closing quote
+                }
             }
             // Analyses if the current token is an ascii_digit and parses it as a Number token
             // In future releases, the language will implement different types of numerical values
             // and different types of operations depending on the type of numerical value given to the interpreter/compiler
             c if c.is_ascii_digit() => {
-                let start = pos;
-                while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
-                    pos += 1;
-                }
-                let number_str: String = chars[start..pos].iter().collect();
-                if let Ok(num) = number_str.parse::<f64>() {
-                    tokens.push(Token::Number(num));
-                } else {
-                    tokens.push(Token::Unknown(number_str));
-                }
+                let (token, end) = scan_number(&chars, pos);
+                tokens.push(token);
+                pos = end;
             }
-            // Gives names to variables (identifiers)
-            c if c.is_ascii_alphabetic() => {
-                let start = pos;
-                while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_')
-                {
-                    pos += 1;
-                }
-                let ident: String = chars[start..pos].iter().collect();
+            // A digit separator cannot start a number: `_1` is scanned as one unknown token
+            '_' if pos + 1 < chars.len() && chars[pos + 1].is_ascii_digit() => {
+                let (token, end) = scan_number(&chars, pos);
+                tokens.push(token);
+                pos = end;
+            }
+            // Gives names to variables (identifiers), which start with a letter or an
+            // underscore; `_` followed by a digit was handled above as a malformed number
+            c if is_identifier_start(c) => {
+                let (ident, end) = scan_identifier(&chars, pos);
+                pos = end;
                 match ident.as_str() {
                     "var" => tokens.push(Token::Var),
+                    "if" => tokens.push(Token::If),
+                    "then" => tokens.push(Token::Then),
+                    "else" => tokens.push(Token::Else),
+                    "while" => tokens.push(Token::While),
+                    "do" => tokens.push(Token::Do),
+                    "fn" => tokens.push(Token::Fn),
+                    "return" => tokens.push(Token::Return),
+                    // `begin name` and `end name` delimit a named section; without a
+                    // name on the same line they are ordinary identifiers
+                    "begin" | "end" => match scan_section_name(&chars, pos) {
+                        Some((name, end)) => {
+                            pos = end;
+                            if ident == "begin" {
+                                tokens.push(Token::BeginSection(name));
+                            } else {
+                                tokens.push(Token::EndSection(name));
+                            }
+                        }
+                        None => tokens.push(Token::Identifier(ident)),
+                    },
                     _ => tokens.push(Token::Identifier(ident)),
                 }
             }
@@ -79,7 +221,282 @@ pub fn tokenize(source: &str) -> Vec<Token> {
                 pos += 1;
             }
         }
+        // Every token pushed by this step starts where the step started
+        positions.resize(tokens.len(), token_start);
+    }
+
+    tokens.into_iter().zip(positions).collect()
+}
+
+// Whether a number literal starts at `pos`: a digit, or a `.` followed by a digit
+fn starts_number(chars: &[char], pos: usize) -> bool {
+    match chars.get(pos) {
+        Some(c) if c.is_ascii_digit() => true,
+        Some('.') => chars.get(pos + 1).is_some_and(|c| c.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+// Whether the next character from `pos` on, past spaces on the same line, is a `^`
+fn followed_by_power(chars: &[char], pos: usize) -> bool {
+    chars[pos..]
+        .iter()
+        .find(|c| !c.is_whitespace() || **c == '\n')
+        .is_some_and(|c| *c == '^')
+}
+
+// Whether the character right before `pos` ends a name or a parenthesized group
+fn follows_operand(chars: &[char], pos: usize) -> bool {
+    pos > 0 && (chars[pos - 1].is_ascii_alphanumeric() || chars[pos - 1] == '_' || chars[pos - 1] == ')')
+}
+
+// Whether `c` can start an identifier: an ascii letter or an underscore
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+// Scans an identifier starting at `start`, which must be an identifier start
+fn scan_identifier(chars: &[char], start: usize) -> (String, usize) {
+    let mut pos = start;
+    while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_') {
+        pos += 1;
+    }
+    (chars[start..pos].iter().collect(), pos)
+}
+
+// Scans the name following a `begin`/`end` keyword, separated from it by
+// spaces or tabs only
+fn scan_section_name(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut pos = start;
+    while pos < chars.len() && (chars[pos] == ' ' || chars[pos] == '\t') {
+        pos += 1;
+    }
+    if pos == start || pos == chars.len() || !is_identifier_start(chars[pos]) {
+        return None;
+    }
+    Some(scan_identifier(chars, pos))
+}
+
+/// Rebuild canonical source text from a token stream
+///
+/// Tokens are separated by single spaces, except inside parentheses, before
+/// commas, between a function name and its argument list and after a prefix
+/// `-`/`+`. Comments and section markers end their line. Tokenizing the
+/// result gives back the same tokens, as long as they came from `tokenize`
+/// and contain no `Error` token.
+pub fn detokenize(tokens: &[Token]) -> String {
+    let mut source = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let before = &tokens[..i];
+        match before.last() {
+            Some(Token::Newline) | None => {}
+            _ if *token == Token::Newline => {}
+            // These run to the end of their line
+            Some(Token::Comment(_)) | Some(Token::BeginSection(_)) | Some(Token::EndSection(_)) => {
+                source.push('\n')
+            }
+            Some(previous) if needs_space(before, previous, token) => source.push(' '),
+            _ => {}
+        }
+        source.push_str(&token_text(token));
+    }
+    source
+}
+
+// Whether a space goes between `previous`, the last of `before`, and `token`
+fn needs_space(before: &[Token], previous: &Token, token: &Token) -> bool {
+    match (previous, token) {
+        (Token::LParen, _) | (_, Token::RParen) | (_, Token::Comma) | (_, Token::Semicolon) => false,
+        (Token::Identifier(_), Token::LParen) => false,
+        // A dot sticks to its neighbours, unless one of them is a number it
+        // would be read back as part of
+        (Token::Dot, Token::Integer(_) | Token::Number(_))
+        | (Token::Integer(_) | Token::Number(_), Token::Dot) => true,
+        (Token::Dot, _) | (_, Token::Dot) => false,
+        // A prefix sign sticks to its operand, but stays apart from a number
+        // so that it is not read back as part of the literal
+        (Token::Operator(op), _)
+            if (op == "-" || op == "+") && is_unary_position(&before[..before.len() - 1]) =>
+        {
+            matches!(token, Token::Integer(_) | Token::Number(_))
+        }
+        _ => true,
+    }
+}
+
+// Source text of a single token
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Var => "var".to_string(),
+        Token::If => "if".to_string(),
+        Token::Then => "then".to_string(),
+        Token::Else => "else".to_string(),
+        Token::While => "while".to_string(),
+        Token::Do => "do".to_string(),
+        Token::Fn => "fn".to_string(),
+        Token::Return => "return".to_string(),
+        Token::Identifier(name) => name.clone(),
+        Token::Assign => ":=".to_string(),
+        Token::Integer(value) => value.to_string(),
+        // Debug formatting keeps the decimal point of whole numbers, e.g. `2.0`
+        Token::Number(value) => format!("{:?}", value),
+        Token::StringLiteral(value) => format!("\"{}\"", value),
+        Token::Operator(op) => op.clone(),
+        Token::LParen => "(".to_string(),
+        Token::RParen => ")".to_string(),
+        Token::Comma => ",".to_string(),
+        Token::Dot => ".".to_string(),
+        Token::Semicolon => ";".to_string(),
+        Token::Newline => "\n".to_string(),
+        Token::Question => "?".to_string(),
+        Token::Colon => ":".to_string(),
+        Token::BeginSection(name) => format!("begin {}", name),
+        Token::EndSection(name) => format!("end {}", name),
+        Token::Comment(text) if text.is_empty() => "#".to_string(),
+        Token::Comment(text) => format!("# {}", text),
+        Token::Unknown(text) => text.clone(),
+        Token::Error(LexError::UnexpectedInput { text, .. }) => text.clone(),
+        // The text of an unterminated string is not kept in the token
+        Token::Error(LexError::UnterminatedString(_)) => "\"".to_string(),
+    }
+}
+
+/// Return the first lexical error in a token stream, if any
+pub fn check_tokens(tokens: &[Token]) -> Result<(), LexError> {
+    match tokens.iter().find_map(|token| match token {
+        Token::Error(error) => Some(error),
+        _ => None,
+    }) {
+        Some(error) => Err(error.clone()),
+        None => Ok(()),
+    }
+}
+
+/// Return an error for the first `Unknown` token of a positioned token stream, if any
+pub fn check_unknown(tokens: &[(Token, usize)]) -> Result<(), LexError> {
+    for (token, position) in tokens {
+        if let Token::Unknown(text) = token {
+            return Err(LexError::UnexpectedInput {
+                text: text.clone(),
+                position: *position,
+            });
+        }
+    }
+    Ok(())
+}
+
+// A `-` is unary when there is nothing on its left that it could subtract from
+fn is_unary_position(tokens: &[Token]) -> bool {
+    matches!(
+        tokens.last(),
+        None | Some(Token::Operator(_))
+            | Some(Token::Assign)
+            | Some(Token::LParen)
+            | Some(Token::Comma)
+            | Some(Token::Semicolon)
+            | Some(Token::Newline)
+            | Some(Token::Question)
+            | Some(Token::Colon)
+            | Some(Token::BeginSection(_))
+    )
+}
+
+// Scans a number literal starting at `start` (optionally at a leading unary `-`,
+// and possibly at its decimal point, as in `.5`)
+// and returns the resulting token along with the position right after it
+fn scan_number(chars: &[char], start: usize) -> (Token, usize) {
+    let mut pos = start;
+    if chars[pos] == '-' {
+        pos += 1;
+    }
+    // Hexadecimal (`0x`) and binary (`0b`) integer literals
+    if chars[pos] == '0' && pos + 1 < chars.len() {
+        let radix = match chars[pos + 1] {
+            'x' | 'X' => Some(16),
+            'b' | 'B' => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            return scan_radix_number(chars, start, pos + 2, radix);
+        }
+    }
+    while pos < chars.len() && (is_digit_or_separator(chars[pos]) || chars[pos] == '.') {
+        pos += 1;
+    }
+    // Optional exponent part (`e`/`E`, optional sign, at least one digit)
+    if pos < chars.len() && (chars[pos] == 'e' || chars[pos] == 'E') {
+        pos += 1;
+        if pos < chars.len() && (chars[pos] == '+' || chars[pos] == '-') {
+            pos += 1;
+        }
+        let exponent_start = pos;
+        while pos < chars.len() && is_digit_or_separator(chars[pos]) {
+            pos += 1;
+        }
+        if pos == exponent_start {
+            // Malformed exponent like `1e` or `1e+`
+            let malformed: String = chars[start..pos].iter().collect();
+            return (Token::Unknown(malformed), pos);
+        }
+    }
+    let number_str: String = chars[start..pos].iter().collect();
+    if !has_valid_separators(&chars[start..pos]) {
+        return (Token::Unknown(number_str), pos);
+    }
+    let digits = number_str.replace('_', "");
+    // Literals without a decimal point or exponent are integers, unless too large for one
+    if digits.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        if let Ok(num) = digits.parse::<i64>() {
+            return (Token::Integer(num), pos);
+        }
     }
+    match digits.parse::<f64>() {
+        Ok(num) => (Token::Number(num), pos),
+        Err(_) => (Token::Unknown(number_str), pos),
+    }
+}
+
+// Scans the digits of a `0x`/`0b` literal, `digits_start` being right after the prefix
+fn scan_radix_number(chars: &[char], start: usize, digits_start: usize, radix: u32) -> (Token, usize) {
+    let mut pos = digits_start;
+    // Any letter or digit belongs to the literal so that `0xG` is rejected as a whole
+    while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_') {
+        pos += 1;
+    }
+    let literal: String = chars[start..pos].iter().collect();
+    let digits = &chars[digits_start..pos];
+    let valid = !digits.is_empty()
+        && digits.first() != Some(&'_')
+        && digits.last() != Some(&'_')
+        && !digits.windows(2).any(|pair| pair == ['_', '_'])
+        && digits.iter().all(|c| *c == '_' || c.is_digit(radix));
+    if !valid {
+        return (Token::Unknown(literal), pos);
+    }
+    let digits: String = digits.iter().filter(|c| **c != '_').collect();
+    let negative = chars[start] == '-';
+    match u64::from_str_radix(&digits, radix) {
+        Ok(value) => match i64::try_from(value) {
+            Ok(value) if negative => (Token::Integer(-value), pos),
+            Ok(value) => (Token::Integer(value), pos),
+            // Too large for an integer, kept as a float
+            Err(_) if negative => (Token::Number(-(value as f64)), pos),
+            Err(_) => (Token::Number(value as f64), pos),
+        },
+        Err(_) => (Token::Unknown(literal), pos),
+    }
+}
+
+fn is_digit_or_separator(c: char) -> bool {
+    c.is_ascii_digit() || c == '_'
+}
 
-    tokens
+// Digit separators are only allowed between two digits, as in `1_000_000`
+fn has_valid_separators(literal: &[char]) -> bool {
+    literal.iter().enumerate().all(|(i, &c)| {
+        c != '_'
+            || (i > 0
+                && literal[i - 1].is_ascii_digit()
+                && literal.get(i + 1).is_some_and(|next| next.is_ascii_digit()))
+    })
 }
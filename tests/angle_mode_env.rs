@@ -0,0 +1,33 @@
+// The initial angle mode taken from OAK_ANGLE_MODE. Environment variables are
+// shared by the whole process, so these checks live in their own test binary
+// and run one after the other in a single test.
+use oak::compiler::VM;
+use oak::interpreter::{AngleMode, Interpreter, ANGLE_MODE_VAR};
+
+#[test]
+fn test_angle_mode_from_environment() {
+    std::env::remove_var(ANGLE_MODE_VAR);
+    assert_eq!(Interpreter::new().angle_mode(), AngleMode::Radians);
+
+    std::env::set_var(ANGLE_MODE_VAR, "deg");
+    assert_eq!(Interpreter::new().angle_mode(), AngleMode::Degrees);
+    assert_eq!(VM::new().angle_mode(), AngleMode::Degrees);
+    assert_eq!(oak::eval("sin(90)").unwrap(), oak::parser::Value::Number(1.0));
+
+    std::env::set_var(ANGLE_MODE_VAR, "rad");
+    assert_eq!(Interpreter::new().angle_mode(), AngleMode::Radians);
+
+    // Invalid values fall back to radians
+    for invalid in ["degrees", "DEG", ""] {
+        std::env::set_var(ANGLE_MODE_VAR, invalid);
+        assert_eq!(Interpreter::new().angle_mode(), AngleMode::Radians);
+    }
+
+    // Changing the variable later does not affect existing interpreters
+    std::env::set_var(ANGLE_MODE_VAR, "deg");
+    let mut interpreter = Interpreter::new();
+    std::env::remove_var(ANGLE_MODE_VAR);
+    assert_eq!(interpreter.angle_mode(), AngleMode::Degrees);
+    interpreter.set_angle_mode(AngleMode::Radians);
+    assert_eq!(interpreter.angle_mode(), AngleMode::Radians);
+}
@@ -0,0 +1,44 @@
+// Command line tests, checking what the `oak` binary prints
+use std::fs;
+use std::path::PathBuf;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+// Writes `source` to a script named `name` in the temporary directory
+fn script(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn test_script_output_has_no_trace_lines() {
+    let path = script("oak_cli_quiet.oak", "var x := 3\nvar y := max(x, 4) * 2\n# done\n");
+
+    Command::cargo_bin("oak")
+        .unwrap()
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n8\n"))
+        .stdout(predicate::str::contains("Assigning").not())
+        .stdout(predicate::str::contains("Variable '").not())
+        .stdout(predicate::str::contains("Result of").not())
+        .stdout(predicate::str::contains("Comment").not());
+}
+
+#[test]
+fn test_debug_flag_traces_evaluation() {
+    let path = script("oak_cli_verbose.oak", "var x := 3\nx * 2\n");
+
+    Command::cargo_bin("oak")
+        .unwrap()
+        .arg("-d")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Assigning 3 to 'x'"))
+        .stdout(predicate::str::contains("Variable 'x' = 3"))
+        .stdout(predicate::str::contains("\n6\n"));
+}